@@ -1,4 +1,6 @@
 use super::*;
+use crate::ieee80211::ReasonCode;
+use tokio::time::Duration;
 
 #[derive(Debug)]
 pub(crate) enum Request {
@@ -8,12 +10,31 @@ pub(crate) enum Request {
     Enable(oneshot::Sender<Result>),
     Disable(oneshot::Sender<Result>),
     SetValue(String, String, oneshot::Sender<Result>),
+    ListStations(oneshot::Sender<Result<Vec<StationInfo>>>),
+    GetStation(MacAddr, oneshot::Sender<Result<StationInfo>>),
+    RemoveStation(RemoveStation, oneshot::Sender<Result>),
+    Acl(Acl, AclCommand, oneshot::Sender<Result>),
+    AclShow(Acl, oneshot::Sender<Result<Vec<AclEntry>>>),
+    AclReplace(Acl, Vec<AclEntry>, oneshot::Sender<Result>),
+    WpsPbc(oneshot::Sender<Result>),
+    WpsPin(WpsPin, oneshot::Sender<Result>),
+    WpsApPin(WpsApPin, oneshot::Sender<Result<Option<String>>>),
+    WpsCancel(oneshot::Sender<Result>),
+    WpsStatus(oneshot::Sender<Result<WpsStatus>>),
+    Ping(oneshot::Sender<Result<Duration>>),
+    SetLogLevel(Level, oneshot::Sender<Result>),
     Shutdown,
+    /// Handles the inner request with a timeout other than the default one
+    WithTimeout(Duration, Box<Request>),
+    RemoveStationTimeout(MacAddr),
 }
 
 impl ShutdownSignal for Request {
     fn is_shutdown(&self) -> bool {
-        matches!(self, Request::Shutdown)
+        match self {
+            Request::WithTimeout(_, request) => request.is_shutdown(),
+            request => matches!(request, Request::Shutdown),
+        }
     }
     fn inform_of_shutdown(self) {
         match self {
@@ -32,23 +53,116 @@ impl ShutdownSignal for Request {
             Request::SetValue(_, _, response) => {
                 let _ = response.send(Err(error::Error::StartupAborted));
             }
+            Request::ListStations(response) => {
+                let _ = response.send(Err(error::Error::StartupAborted));
+            }
+            Request::GetStation(_, response) => {
+                let _ = response.send(Err(error::Error::StartupAborted));
+            }
+            Request::RemoveStation(_, response) => {
+                let _ = response.send(Err(error::Error::StartupAborted));
+            }
+            Request::Acl(_, _, response) | Request::AclReplace(_, _, response) => {
+                let _ = response.send(Err(error::Error::StartupAborted));
+            }
+            Request::AclShow(_, response) => {
+                let _ = response.send(Err(error::Error::StartupAborted));
+            }
+            Request::WpsPbc(response)
+            | Request::WpsPin(_, response)
+            | Request::WpsCancel(response) => {
+                let _ = response.send(Err(error::Error::StartupAborted));
+            }
+            Request::WpsApPin(_, response) => {
+                let _ = response.send(Err(error::Error::StartupAborted));
+            }
+            Request::WpsStatus(response) => {
+                let _ = response.send(Err(error::Error::StartupAborted));
+            }
+            Request::Ping(response) => {
+                let _ = response.send(Err(error::Error::StartupAborted));
+            }
+            Request::SetLogLevel(_, response) => {
+                let _ = response.send(Err(error::Error::StartupAborted));
+            }
             Request::Shutdown => {}
+            Request::WithTimeout(_, request) => request.inform_of_shutdown(),
+            Request::RemoveStationTimeout(_) => {}
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) enum RemoveStation {
+    Deauthenticate(MacAddr, ReasonCode),
+    Disassociate(MacAddr, ReasonCode),
+}
+
+impl RemoveStation {
+    pub fn mac(&self) -> MacAddr {
+        match self {
+            RemoveStation::Deauthenticate(mac, _) | RemoveStation::Disassociate(mac, _) => *mac,
         }
     }
 }
 
+#[derive(Debug)]
+pub(crate) enum AclCommand {
+    Add(AclEntry),
+    Remove(MacAddr),
+    Clear,
+}
+
+#[derive(Debug)]
+pub(crate) struct WpsPin {
+    pub uuid: Option<String>,
+    pub pin: String,
+    pub timeout: Option<Duration>,
+}
+
+#[derive(Debug, Clone)]
+/// Operations on the AP PIN, which lets external registrars configure the Access Point over WPS.
+pub enum WpsApPin {
+    /// Generate a random AP PIN, optionally only valid for the given duration
+    Random(Option<Duration>),
+    /// Set the AP PIN, optionally only valid for the given duration
+    Set(String, Option<Duration>),
+    Disable,
+    Get,
+}
+
 #[derive(Clone)]
 /// Request client wraps the request events, awaiting oneshot channels when appropriate
 pub struct RequestClient {
     sender: mpsc::Sender<Request>,
+    /// Overrides the default request timeout for requests made through this client
+    timeout: Option<Duration>,
 }
 
 impl RequestClient {
     pub(crate) fn new(sender: mpsc::Sender<Request>) -> RequestClient {
-        RequestClient { sender }
+        RequestClient {
+            sender,
+            timeout: None,
+        }
+    }
+
+    /// Returns a client whose requests wait up to `timeout` for each response from the daemon
+    /// instead of the default request timeout.
+    pub fn with_timeout(&self, timeout: Duration) -> RequestClient {
+        RequestClient {
+            sender: self.sender.clone(),
+            timeout: Some(timeout),
+        }
     }
 
     async fn send_request(&self, request: Request) -> Result {
+        let request = match self.timeout {
+            Some(timeout) if !request.is_shutdown() => {
+                Request::WithTimeout(timeout, Box::new(request))
+            }
+            _ => request,
+        };
         self.sender
             .send(request)
             .await
@@ -58,10 +172,7 @@ impl RequestClient {
 
     pub async fn send_custom(&self, custom: String) -> Result<String> {
         let (response, request) = oneshot::channel();
-        self.sender
-            .send(Request::Custom(custom, response))
-            .await
-            .map_err(|_| error::Error::WifiApRequestChannelClosed)?;
+        self.send_request(Request::Custom(custom, response)).await?;
         request.await?
     }
 
@@ -96,19 +207,232 @@ impl RequestClient {
         request.await?
     }
 
+    /// List the stations currently known to hostapd, walking `STA-FIRST`/`STA-NEXT`.
+    pub async fn list_stations(&self) -> Result<Vec<StationInfo>> {
+        let (response, request) = oneshot::channel();
+        self.send_request(Request::ListStations(response)).await?;
+        request.await?
+    }
+
+    pub async fn get_station(&self, mac: MacAddr) -> Result<StationInfo> {
+        let (response, request) = oneshot::channel();
+        self.send_request(Request::GetStation(mac, response))
+            .await?;
+        request.await?
+    }
+
+    /// Deauthenticate a station, resolving once hostapd reports it as disconnected.
+    pub async fn deauthenticate(&self, mac: MacAddr, reason: ReasonCode) -> Result {
+        let (response, request) = oneshot::channel();
+        self.send_request(Request::RemoveStation(
+            RemoveStation::Deauthenticate(mac, reason),
+            response,
+        ))
+        .await?;
+        request.await?
+    }
+
+    /// Disassociate a station, resolving once hostapd reports it as disconnected.
+    pub async fn disassociate(&self, mac: MacAddr, reason: ReasonCode) -> Result {
+        let (response, request) = oneshot::channel();
+        self.send_request(Request::RemoveStation(
+            RemoveStation::Disassociate(mac, reason),
+            response,
+        ))
+        .await?;
+        request.await?
+    }
+
+    /// Add a station to the access control list, optionally with the VLAN it should be put in.
+    pub async fn acl_add_mac(&self, acl: Acl, mac: MacAddr, vlan_id: Option<u16>) -> Result {
+        let (response, request) = oneshot::channel();
+        self.send_request(Request::Acl(
+            acl,
+            AclCommand::Add(AclEntry { mac, vlan_id }),
+            response,
+        ))
+        .await?;
+        request.await?
+    }
+
+    pub async fn acl_del_mac(&self, acl: Acl, mac: MacAddr) -> Result {
+        let (response, request) = oneshot::channel();
+        self.send_request(Request::Acl(acl, AclCommand::Remove(mac), response))
+            .await?;
+        request.await?
+    }
+
+    pub async fn acl_show(&self, acl: Acl) -> Result<Vec<AclEntry>> {
+        let (response, request) = oneshot::channel();
+        self.send_request(Request::AclShow(acl, response)).await?;
+        request.await?
+    }
+
+    pub async fn acl_clear(&self, acl: Acl) -> Result {
+        let (response, request) = oneshot::channel();
+        self.send_request(Request::Acl(acl, AclCommand::Clear, response))
+            .await?;
+        request.await?
+    }
+
+    /// Replace the whole access control list in one request. No other request is handled until
+    /// the list is updated, and entries present in both lists remain in place throughout.
+    pub async fn acl_replace(&self, acl: Acl, entries: Vec<AclEntry>) -> Result {
+        let (response, request) = oneshot::channel();
+        self.send_request(Request::AclReplace(acl, entries, response))
+            .await?;
+        request.await?
+    }
+
+    /// Activate WPS push button for the walk time (two minutes).
+    pub async fn wps_pbc(&self) -> Result {
+        let (response, request) = oneshot::channel();
+        self.send_request(Request::WpsPbc(response)).await?;
+        request.await?
+    }
+
+    /// Add an enrollee PIN. If `uuid` is `None`, any enrollee may use the PIN. If `timeout` is
+    /// `None`, the PIN remains valid until used.
+    pub async fn wps_pin(
+        &self,
+        uuid: Option<String>,
+        pin: String,
+        timeout: Option<Duration>,
+    ) -> Result {
+        let (response, request) = oneshot::channel();
+        self.send_request(Request::WpsPin(WpsPin { uuid, pin, timeout }, response))
+            .await?;
+        request.await?
+    }
+
+    /// Manage the AP PIN. The PIN in use is returned, except when disabling it.
+    pub async fn wps_ap_pin(&self, action: WpsApPin) -> Result<Option<String>> {
+        let (response, request) = oneshot::channel();
+        self.send_request(Request::WpsApPin(action, response))
+            .await?;
+        request.await?
+    }
+
+    pub async fn wps_cancel(&self) -> Result {
+        let (response, request) = oneshot::channel();
+        self.send_request(Request::WpsCancel(response)).await?;
+        request.await?
+    }
+
+    pub async fn wps_get_status(&self) -> Result<WpsStatus> {
+        let (response, request) = oneshot::channel();
+        self.send_request(Request::WpsStatus(response)).await?;
+        request.await?
+    }
+
+    /// Checks that hostapd is responsive, returning the round-trip time of the request.
+    pub async fn ping(&self) -> Result<Duration> {
+        let (response, request) = oneshot::channel();
+        self.send_request(Request::Ping(response)).await?;
+        request.await?
+    }
+
+    /// Sets the level of the debug output of hostapd. Events below the minimum event level
+    /// configured in the setup are not broadcast regardless of this level.
+    pub async fn set_log_level(&self, level: Level) -> Result {
+        let (response, request) = oneshot::channel();
+        self.send_request(Request::SetLogLevel(level, response))
+            .await?;
+        request.await?
+    }
+
     pub async fn shutdown(&self) -> Result {
         self.send_request(Request::Shutdown).await
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 /// Broadcast events, such as a client disconnecting or connecting, may happen at any time.
 pub enum Broadcast {
+    /// The connection to hostapd was established, initially or after reconnecting
     Ready,
-    Connected(String),
-    Disconnected(String),
+    /// hostapd terminated or its socket failed. Requests are deferred until it is back and
+    /// `Ready` is broadcast again.
+    DaemonLost,
+    /// hostapd failed to answer the configured number of consecutive keep-alive pings
+    Unresponsive,
+    /// hostapd answered a keep-alive ping after being reported as unresponsive
+    Recovered,
+    /// A station connected, with the P2P device address or the identifier of the PSK it used
+    /// when reported
+    Connected {
+        mac: MacAddr,
+        p2p_dev_addr: Option<MacAddr>,
+        keyid: Option<String>,
+    },
+    Disconnected(MacAddr),
+    /// A station failed the 4-way handshake, most likely because it used the wrong passphrase
+    PossiblePskMismatch(MacAddr),
+    FourWayHandshakeCompleted(MacAddr),
+    ApEnabled,
+    ApDisabled,
+    InterfaceEnabled,
+    InterfaceDisabled,
+    /// hostapd is shutting down
+    Terminating,
+    EapStarted(MacAddr),
+    EapSuccess(MacAddr),
+    EapFailure(MacAddr),
+    /// Any other `CTRL-EVENT-EAP-<event>`
+    Eap {
+        event: String,
+        mac: Option<MacAddr>,
+    },
+    /// A channel switch completed
+    CsaFinished {
+        freq: Option<u32>,
+        dfs: bool,
+    },
+    DfsRadarDetected(DfsInfo),
+    DfsNewChannel(DfsInfo),
+    DfsCacStart(DfsInfo),
+    DfsCacCompleted(DfsInfo),
+    DfsNopFinished(DfsInfo),
+    DfsPreCacExpired(DfsInfo),
+    AcsStarted,
+    AcsCompleted {
+        freq: Option<u32>,
+        channel: Option<u8>,
+    },
+    AcsFailed,
+    WpsPbcActive,
+    WpsPbcDisabled,
+    /// An enrollee received the credentials
+    WpsSuccess,
+    /// An enrollee was registered
+    WpsRegSuccess {
+        mac: MacAddr,
+        uuid: String,
+    },
+    WpsFail {
+        config_error: Option<u16>,
+    },
+    WpsTimeout,
+    WpsPbcOverlap,
     UnknownEvent(String),
 }
 
 /// Channel for broadcasting events.
-pub type BroadcastReceiver = broadcast::Receiver<Broadcast>;
+pub type BroadcastReceiver = broadcast::Receiver<BroadcastMessage>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A broadcast along with the level of the hostapd message it originated from. Broadcasts the
+/// runtime generates itself, such as `Ready`, have no level.
+pub struct BroadcastMessage {
+    pub level: Option<Level>,
+    pub broadcast: Broadcast,
+}
+
+impl From<Broadcast> for BroadcastMessage {
+    fn from(broadcast: Broadcast) -> Self {
+        BroadcastMessage {
+            level: None,
+            broadcast,
+        }
+    }
+}
@@ -0,0 +1,270 @@
+use super::*;
+use crate::parsing::{event_field, split_level};
+
+/// Parses an unsolicited message from hostapd into a typed broadcast. Messages which are not
+/// recognized (including debug output enabled by `LOG_LEVEL`) become `Broadcast::UnknownEvent`,
+/// without their level prefix, which is carried in `BroadcastMessage::level` instead.
+pub(crate) fn parse_event(msg: &str) -> BroadcastMessage {
+    let (level, event) = split_level(msg);
+    let broadcast =
+        parse_known_event(event).unwrap_or_else(|| Broadcast::UnknownEvent(event.into()));
+    BroadcastMessage { level, broadcast }
+}
+
+fn parse_known_event(event: &str) -> Option<Broadcast> {
+    let (name, args) = event.split_once(' ').unwrap_or((event, ""));
+    let mac = || {
+        args.split_whitespace()
+            .next()
+            .and_then(|mac| MacAddr::from_str(mac).ok())
+    };
+    let field = |key| event_field(args, key);
+    let broadcast = match name {
+        "AP-STA-CONNECTED" => Broadcast::Connected {
+            mac: mac()?,
+            p2p_dev_addr: field("p2p_dev_addr").and_then(|mac| MacAddr::from_str(&mac).ok()),
+            keyid: field("keyid"),
+        },
+        "AP-STA-DISCONNECTED" => Broadcast::Disconnected(mac()?),
+        "AP-STA-POSSIBLE-PSK-MISMATCH" => Broadcast::PossiblePskMismatch(mac()?),
+        "EAPOL-4WAY-HS-COMPLETED" => Broadcast::FourWayHandshakeCompleted(mac()?),
+        "AP-ENABLED" => Broadcast::ApEnabled,
+        "AP-DISABLED" => Broadcast::ApDisabled,
+        "INTERFACE-ENABLED" => Broadcast::InterfaceEnabled,
+        "INTERFACE-DISABLED" => Broadcast::InterfaceDisabled,
+        "CTRL-EVENT-TERMINATING" => Broadcast::Terminating,
+        "CTRL-EVENT-EAP-STARTED" => Broadcast::EapStarted(mac()?),
+        "CTRL-EVENT-EAP-SUCCESS" => Broadcast::EapSuccess(mac()?),
+        "CTRL-EVENT-EAP-FAILURE" => Broadcast::EapFailure(mac()?),
+        eap if eap.starts_with("CTRL-EVENT-EAP-") => Broadcast::Eap {
+            event: eap.trim_start_matches("CTRL-EVENT-EAP-").to_string(),
+            mac: mac(),
+        },
+        "AP-CSA-FINISHED" => Broadcast::CsaFinished {
+            freq: field("freq").and_then(|freq| freq.parse().ok()),
+            dfs: field("dfs").as_deref() == Some("1"),
+        },
+        "DFS-RADAR-DETECTED" => Broadcast::DfsRadarDetected(DfsInfo::from_args(args)),
+        "DFS-NEW-CHANNEL" => Broadcast::DfsNewChannel(DfsInfo::from_args(args)),
+        "DFS-CAC-START" => Broadcast::DfsCacStart(DfsInfo::from_args(args)),
+        "DFS-CAC-COMPLETED" => Broadcast::DfsCacCompleted(DfsInfo::from_args(args)),
+        "DFS-NOP-FINISHED" => Broadcast::DfsNopFinished(DfsInfo::from_args(args)),
+        "DFS-PRE-CAC-EXPIRED" => Broadcast::DfsPreCacExpired(DfsInfo::from_args(args)),
+        "ACS-STARTED" => Broadcast::AcsStarted,
+        "ACS-COMPLETED" => Broadcast::AcsCompleted {
+            freq: field("freq").and_then(|freq| freq.parse().ok()),
+            channel: field("channel").and_then(|channel| channel.parse().ok()),
+        },
+        "ACS-FAILED" => Broadcast::AcsFailed,
+        "WPS-PBC-ACTIVE" => Broadcast::WpsPbcActive,
+        "WPS-PBC-DISABLE" => Broadcast::WpsPbcDisabled,
+        "WPS-SUCCESS" => Broadcast::WpsSuccess,
+        "WPS-REG-SUCCESS" => Broadcast::WpsRegSuccess {
+            mac: mac()?,
+            uuid: args.split_whitespace().nth(1).unwrap_or_default().into(),
+        },
+        "WPS-FAIL" => Broadcast::WpsFail {
+            config_error: field("config_error").and_then(|code| code.parse().ok()),
+        },
+        "WPS-TIMEOUT" => Broadcast::WpsTimeout,
+        "WPS-PBC-OVERLAP" => Broadcast::WpsPbcOverlap,
+        _ => return None,
+    };
+    Some(broadcast)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mac(s: &str) -> MacAddr {
+        MacAddr::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn parses_hostapd_events() {
+        let cases = [
+            (
+                "<3>AP-STA-CONNECTED 02:00:00:00:01:00",
+                Broadcast::Connected {
+                    mac: mac("02:00:00:00:01:00"),
+                    p2p_dev_addr: None,
+                    keyid: None,
+                },
+            ),
+            (
+                "<3>AP-STA-CONNECTED 02:00:00:00:01:00 p2p_dev_addr=02:00:00:00:02:00",
+                Broadcast::Connected {
+                    mac: mac("02:00:00:00:01:00"),
+                    p2p_dev_addr: Some(mac("02:00:00:00:02:00")),
+                    keyid: None,
+                },
+            ),
+            (
+                "<3>AP-STA-CONNECTED 02:00:00:00:01:00 keyid=guest",
+                Broadcast::Connected {
+                    mac: mac("02:00:00:00:01:00"),
+                    p2p_dev_addr: None,
+                    keyid: Some("guest".into()),
+                },
+            ),
+            (
+                "<3>AP-STA-DISCONNECTED 02:00:00:00:01:00",
+                Broadcast::Disconnected(mac("02:00:00:00:01:00")),
+            ),
+            (
+                "<3>AP-STA-POSSIBLE-PSK-MISMATCH 02:00:00:00:01:00",
+                Broadcast::PossiblePskMismatch(mac("02:00:00:00:01:00")),
+            ),
+            (
+                "<3>EAPOL-4WAY-HS-COMPLETED 02:00:00:00:01:00",
+                Broadcast::FourWayHandshakeCompleted(mac("02:00:00:00:01:00")),
+            ),
+            ("<3>AP-ENABLED ", Broadcast::ApEnabled),
+            ("<3>AP-DISABLED ", Broadcast::ApDisabled),
+            ("<3>INTERFACE-ENABLED ", Broadcast::InterfaceEnabled),
+            ("<3>INTERFACE-DISABLED ", Broadcast::InterfaceDisabled),
+            ("<3>CTRL-EVENT-TERMINATING ", Broadcast::Terminating),
+            (
+                "IFNAME=wlan1 <3>AP-STA-DISCONNECTED 02:00:00:00:01:00",
+                Broadcast::Disconnected(mac("02:00:00:00:01:00")),
+            ),
+            (
+                "<3>CTRL-EVENT-EAP-STARTED 02:00:00:00:01:00",
+                Broadcast::EapStarted(mac("02:00:00:00:01:00")),
+            ),
+            (
+                "<3>CTRL-EVENT-EAP-SUCCESS 02:00:00:00:01:00",
+                Broadcast::EapSuccess(mac("02:00:00:00:01:00")),
+            ),
+            (
+                "<3>CTRL-EVENT-EAP-FAILURE 02:00:00:00:01:00",
+                Broadcast::EapFailure(mac("02:00:00:00:01:00")),
+            ),
+            (
+                "<3>CTRL-EVENT-EAP-RETRANSMIT 02:00:00:00:01:00",
+                Broadcast::Eap {
+                    event: "RETRANSMIT".into(),
+                    mac: Some(mac("02:00:00:00:01:00")),
+                },
+            ),
+            (
+                "<3>AP-CSA-FINISHED freq=5180 dfs=0",
+                Broadcast::CsaFinished {
+                    freq: Some(5180),
+                    dfs: false,
+                },
+            ),
+            (
+                "<3>DFS-RADAR-DETECTED freq=5260 ht_enabled=1 chan_offset=0 chan_width=1 cf1=5260 cf2=0",
+                Broadcast::DfsRadarDetected(DfsInfo {
+                    freq: Some(5260),
+                    chan_width: Some(1),
+                    cf1: Some(5260),
+                    cf2: Some(0),
+                    ..Default::default()
+                }),
+            ),
+            (
+                "<3>DFS-NEW-CHANNEL freq=5180 chan=36 sec_chan=1",
+                Broadcast::DfsNewChannel(DfsInfo {
+                    freq: Some(5180),
+                    channel: Some(36),
+                    ..Default::default()
+                }),
+            ),
+            (
+                "<3>DFS-CAC-START freq=5260 chan=52 chan_offset=0 width=1 seg0=0 seg1=0 cac_time=60s",
+                Broadcast::DfsCacStart(DfsInfo {
+                    freq: Some(5260),
+                    channel: Some(52),
+                    chan_width: Some(1),
+                    cac_time: Some(60),
+                    ..Default::default()
+                }),
+            ),
+            (
+                "<3>DFS-CAC-COMPLETED success=1 freq=5260 ht_enabled=1 chan_offset=0 chan_width=1 cf1=5260 cf2=0",
+                Broadcast::DfsCacCompleted(DfsInfo {
+                    freq: Some(5260),
+                    chan_width: Some(1),
+                    cf1: Some(5260),
+                    cf2: Some(0),
+                    success: Some(true),
+                    ..Default::default()
+                }),
+            ),
+            (
+                "<3>DFS-NOP-FINISHED freq=5260 ht_enabled=1 chan_offset=0 chan_width=1 cf1=5260 cf2=0",
+                Broadcast::DfsNopFinished(DfsInfo {
+                    freq: Some(5260),
+                    chan_width: Some(1),
+                    cf1: Some(5260),
+                    cf2: Some(0),
+                    ..Default::default()
+                }),
+            ),
+            ("<3>ACS-STARTED ", Broadcast::AcsStarted),
+            (
+                "<3>ACS-COMPLETED freq=2437 channel=6",
+                Broadcast::AcsCompleted {
+                    freq: Some(2437),
+                    channel: Some(6),
+                },
+            ),
+            ("<3>ACS-FAILED ", Broadcast::AcsFailed),
+            ("<3>WPS-PBC-ACTIVE ", Broadcast::WpsPbcActive),
+            ("<3>WPS-PBC-DISABLE ", Broadcast::WpsPbcDisabled),
+            ("<3>WPS-SUCCESS ", Broadcast::WpsSuccess),
+            (
+                "<3>WPS-REG-SUCCESS 02:00:00:00:01:00 2b7093f1-d6fb-5108-adbb-bea66bb87333",
+                Broadcast::WpsRegSuccess {
+                    mac: mac("02:00:00:00:01:00"),
+                    uuid: "2b7093f1-d6fb-5108-adbb-bea66bb87333".into(),
+                },
+            ),
+            (
+                "<3>WPS-FAIL msg=8 config_error=15",
+                Broadcast::WpsFail {
+                    config_error: Some(15),
+                },
+            ),
+            ("<3>WPS-TIMEOUT ", Broadcast::WpsTimeout),
+            ("<3>WPS-PBC-OVERLAP ", Broadcast::WpsPbcOverlap),
+            (
+                "<2>wlan1: STA 02:00:00:00:01:00 IEEE 802.11: associated (aid 1)",
+                Broadcast::UnknownEvent(
+                    "wlan1: STA 02:00:00:00:01:00 IEEE 802.11: associated (aid 1)".into(),
+                ),
+            ),
+            (
+                "<3>AP-STA-CONNECTED not-a-mac",
+                Broadcast::UnknownEvent("AP-STA-CONNECTED not-a-mac".into()),
+            ),
+        ];
+        for (msg, expected) in cases {
+            assert_eq!(
+                parse_event(msg.trim_end()).broadcast,
+                expected,
+                "parsing {msg:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn parses_event_levels() {
+        let cases = [
+            ("<3>AP-ENABLED", Some(Level::Info)),
+            (
+                "<2>wlan1: STA 02:00:00:00:01:00 WPA: pairwise key handshake completed (RSN)",
+                Some(Level::Debug),
+            ),
+            ("IFNAME=wlan1 <4>AP-DISABLED", Some(Level::Warning)),
+            ("<9>AP-ENABLED", None),
+            ("AP-ENABLED", None),
+        ];
+        for (msg, expected) in cases {
+            assert_eq!(parse_event(msg).level, expected, "parsing {msg:?}");
+        }
+    }
+}
@@ -1,35 +1,35 @@
 use super::*;
 
 pub(crate) struct EventSocket {
-    socket_handle: SocketHandle<1024>,
+    socket_handle: SocketHandle,
     attach_options: Vec<String>,
+    /// Minimum level of the events to receive
+    event_level: Level,
     /// Sends messages to client
-    sender: mpsc::Sender<Event>,
+    sender: mpsc::Sender<BroadcastMessage>,
+    /// Receives unsolicited messages which arrived on the request socket
+    unsolicited_receiver: mpsc::UnboundedReceiver<String>,
 }
 
-#[derive(Debug)]
-pub(crate) enum Event {
-    ApStaConnected(String),
-    ApStaDisconnected(String),
-    Unknown(String),
-}
-
-pub(crate) type EventReceiver = mpsc::Receiver<Event>;
+pub(crate) type EventReceiver = mpsc::Receiver<BroadcastMessage>;
 
 impl EventSocket {
     pub(crate) async fn new<P>(
         socket: P,
         request_receiver: &mut mpsc::Receiver<Request>,
         attach_options: &[String],
+        event_level: Level,
     ) -> Result<(EventReceiver, Vec<Request>, Self)>
     where
         P: AsRef<std::path::Path> + std::fmt::Debug,
     {
-        let (socket_handle, deferred_requests) =
+        let (mut socket_handle, deferred_requests) =
             SocketHandle::open(socket, "hostapd_async.sock", request_receiver).await?;
 
         // setup the channel for client requests
         let (sender, receiver) = mpsc::channel(32);
+        let (unsolicited_sender, unsolicited_receiver) = mpsc::unbounded_channel();
+        socket_handle.unsolicited_sender = Some(unsolicited_sender);
         Ok((
             receiver,
             deferred_requests,
@@ -37,11 +37,19 @@ impl EventSocket {
                 socket_handle,
                 sender,
                 attach_options: attach_options.to_vec(),
+                event_level,
+                unsolicited_receiver,
             },
         ))
     }
 
-    async fn send_event(&self, event: Event) -> Result {
+    /// Channel for forwarding unsolicited messages which arrive on another socket to the event
+    /// stream
+    pub(crate) fn unsolicited_sender(&self) -> Option<mpsc::UnboundedSender<String>> {
+        self.socket_handle.unsolicited_sender.clone()
+    }
+
+    async fn send_event(&self, event: BroadcastMessage) -> Result {
         self.sender
             .send(event)
             .await
@@ -49,53 +57,51 @@ impl EventSocket {
         Ok(())
     }
 
+    /// Retries a command until hostapd accepts it. Socket errors mean hostapd went away and are
+    /// returned so that the connection can be re-established.
+    async fn retry_command(&mut self, cmd: &[u8]) -> Result {
+        loop {
+            match self.socket_handle.command(cmd).await {
+                Ok(()) => return Ok(()),
+                Err(error::Error::Io(e)) => return Err(error::Error::UnsolicitedIoError(e)),
+                Err(_) => tokio::time::sleep(tokio::time::Duration::from_millis(250)).await,
+            }
+        }
+    }
+
     pub(crate) async fn run(mut self) -> Result {
         let mut command = "ATTACH".to_string();
         for o in &self.attach_options {
             command.push(' ');
             command.push_str(o);
         }
-        let mut attach = self.socket_handle.command(command.as_bytes()).await;
-        while attach.is_err() {
-            tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
-            attach = self.socket_handle.command(command.as_bytes()).await;
-        }
-
-        let mut log_level = self.socket_handle.command(b"LOG_LEVEL DEBUG").await;
-        while log_level.is_err() {
-            tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
-            log_level = self.socket_handle.command(b"LOG_LEVEL DEBUG").await;
+        self.retry_command(command.as_bytes()).await?;
+        // events up to INFO are needed by the runtime itself, lower ones are filtered before
+        // being broadcast
+        let level = self.event_level.min(Level::Info);
+        self.retry_command(format!("LEVEL {}", level.value()).as_bytes())
+            .await?;
+        // hostapd only emits debug messages if its own log level allows it
+        if level < Level::Info {
+            self.retry_command(format!("LOG_LEVEL {level}").as_bytes())
+                .await?;
         }
         info!("hostapd event stream registered");
 
         loop {
-            match self
-                .socket_handle
-                .socket
-                .recv(&mut self.socket_handle.buffer)
-                .await
-            {
-                Ok(n) => {
-                    let data_str = std::str::from_utf8(&self.socket_handle.buffer[..n])?.trim_end();
-                    if let Some(n) = data_str.find("AP-STA-DISCONNECTED") {
-                        let index = n + "AP-STA-DISCONNECTED".len();
-                        let mac = &data_str[index..];
-                        self.send_event(Event::ApStaDisconnected(mac.to_string()))
-                            .await?;
-                    } else if let Some(n) = data_str.find("AP-STA-CONNECTED") {
-                        let index = n + "AP-STA-CONNECTED".len();
-                        let mac = &data_str[index..];
-                        self.send_event(Event::ApStaConnected(mac.to_string()))
-                            .await?;
-                    } else {
-                        self.send_event(Event::Unknown(data_str.to_string()))
-                            .await?;
+            let data_str = tokio::select!(
+                resp = self.socket_handle.recv() => match resp {
+                    Ok(data_str) => data_str,
+                    Err(error::Error::Io(e)) => return Err(error::Error::UnsolicitedIoError(e)),
+                    Err(e) => {
+                        warn!("Dropping event: {e}");
+                        continue;
                     }
-                }
-                Err(e) => {
-                    return Err(error::Error::UnsolicitedIoError(e));
-                }
-            }
+                },
+                // messages received on the request socket
+                Some(data_str) = self.unsolicited_receiver.recv() => data_str,
+            );
+            self.send_event(parse_event(&data_str)).await?;
         }
     }
 }
@@ -1,5 +1,8 @@
 use super::*;
 
+use crate::keep_alive::{Health, KeepAlive};
+use tokio::time::Duration;
+
 mod types;
 pub use types::*;
 
@@ -12,6 +15,9 @@ pub use setup::*;
 mod event_socket;
 use event_socket::*;
 
+mod event_parser;
+use event_parser::*;
+
 const PATH_DEFAULT_SERVER: &str = "/var/run/hostapd/wlan1";
 
 /// Instance that runs the Wifi process
@@ -20,132 +26,231 @@ pub struct WifiAp {
     socket_path: std::path::PathBuf,
     /// Options to pass to the hostapd attach command
     attach_options: Vec<String>,
+    /// Minimum level of the hostapd events to broadcast
+    event_level: Level,
     /// Channel for receiving requests
     request_receiver: mpsc::Receiver<Request>,
     #[allow(unused)]
     /// Channel for broadcasting alerts
-    broadcast_sender: broadcast::Sender<Broadcast>,
+    broadcast_sender: broadcast::Sender<BroadcastMessage>,
     /// Channel for sending requests to itself
     self_sender: mpsc::Sender<Request>,
+    /// Timeout duration in case a removed station is not reported as disconnected
+    disconnect_timeout: Duration,
+    /// Default time to wait for hostapd to respond to a request
+    request_timeout: Duration,
+    /// Size of the largest response accepted from hostapd
+    max_response_size: usize,
+    /// How often to ping hostapd to check that it is responsive. None disables the health monitor.
+    ping_interval: Option<Duration>,
+    /// Consecutive ping failures after which hostapd is reported as unresponsive
+    ping_failure_threshold: u32,
 }
 
 impl WifiAp {
     pub async fn run(mut self) -> Result {
         info!("Starting Wifi AP process");
+        // Stations being deauthenticated or disassociated, awaiting their disconnection
+        let mut station_removals = Vec::new();
+        loop {
+            match self.run_connection(&mut station_removals).await {
+                Err(error::Error::DaemonLost) | Err(error::Error::UnsolicitedIoError(_)) => {
+                    warn!("Lost connection to hostapd, reconnecting");
+                    if let Err(e) = self.broadcast_sender.send(Broadcast::DaemonLost.into()) {
+                        warn!("error broadcasting: {e}");
+                    }
+                }
+                resp => return resp,
+            }
+        }
+    }
+
+    /// Connects to hostapd and handles events and requests until shutdown or until the
+    /// connection is lost.
+    async fn run_connection(&mut self, station_removals: &mut Vec<StationRemoval>) -> Result {
         let (event_receiver, mut deferred_requests, event_socket) = EventSocket::new(
             &self.socket_path,
             &mut self.request_receiver,
             &self.attach_options,
+            self.event_level,
         )
         .await?;
         // We start up a separate socket for receiving the "unexpected" events that
         // gets forwarded to us via the event_receiver
-        let (socket_handle, next_deferred_requests) = SocketHandle::open(
+        let (mut socket_handle, next_deferred_requests) = SocketHandle::open(
             &self.socket_path,
             "mapper_hostapd_sync.sock",
             &mut self.request_receiver,
         )
         .await?;
+        socket_handle.timeout = self.request_timeout;
+        socket_handle.max_response_size = self.max_response_size;
+        socket_handle.unsolicited_sender = event_socket.unsolicited_sender();
         deferred_requests.extend(next_deferred_requests);
         for request in deferred_requests {
             let _ = self.self_sender.send(request).await;
         }
-        self.broadcast_sender.send(Broadcast::Ready)?;
+        if let Err(e) = self.broadcast_sender.send(Broadcast::Ready.into()) {
+            warn!("error broadcasting: {e}");
+        }
         tokio::select!(
             resp = event_socket.run() => resp,
-            resp = self.run_internal(event_receiver, socket_handle) => resp,
+            resp = self.run_internal(event_receiver, socket_handle, station_removals) => resp,
         )
     }
 
     async fn run_internal(
-        mut self,
+        &mut self,
         mut event_receiver: EventReceiver,
-        mut socket_handle: SocketHandle<2048>,
+        mut socket_handle: SocketHandle,
+        station_removals: &mut Vec<StationRemoval>,
     ) -> Result {
         enum EventOrRequest {
-            Event(Option<Event>),
+            Event(Option<BroadcastMessage>),
             Request(Option<Request>),
+            KeepAlive,
         }
 
+        let mut keep_alive = KeepAlive::new(self.ping_interval, self.ping_failure_threshold);
         loop {
             let event_or_request = tokio::select!(
                 event = event_receiver.recv() => EventOrRequest::Event(event),
                 request = self.request_receiver.recv() => EventOrRequest::Request(request),
+                _ = keep_alive.tick() => EventOrRequest::KeepAlive,
             );
             match event_or_request {
                 EventOrRequest::Event(event) => match event {
                     Some(event) => {
-                        Self::handle_event(&mut socket_handle, &self.broadcast_sender, event)
-                            .await?
+                        let terminating = event.broadcast == Broadcast::Terminating;
+                        Self::handle_event(
+                            &mut socket_handle,
+                            &self.broadcast_sender,
+                            self.event_level,
+                            event,
+                            station_removals,
+                        )
+                        .await?;
+                        if terminating {
+                            return Err(error::Error::DaemonLost);
+                        }
                     }
                     None => return Err(error::Error::WifiApEventChannelClosed),
                 },
                 EventOrRequest::Request(request) => match request {
                     Some(Request::Shutdown) => return Ok(()),
-                    Some(request) => Self::handle_request(&mut socket_handle, request).await?,
+                    Some(Request::WithTimeout(timeout, request)) => {
+                        let default_timeout =
+                            std::mem::replace(&mut socket_handle.timeout, timeout);
+                        let result = self
+                            .handle_request(&mut socket_handle, *request, station_removals)
+                            .await;
+                        socket_handle.timeout = default_timeout;
+                        result?
+                    }
+                    Some(request) => {
+                        self.handle_request(&mut socket_handle, request, station_removals)
+                            .await?
+                    }
                     None => return Err(error::Error::WifiApRequestChannelClosed),
                 },
+                EventOrRequest::KeepAlive => {
+                    Self::handle_keep_alive(
+                        &mut socket_handle,
+                        &self.broadcast_sender,
+                        &mut keep_alive,
+                    )
+                    .await?
+                }
+            }
+            // the caller got the I/O error of its request, reconnect for the next ones
+            if socket_handle.connection_lost {
+                return Err(error::Error::DaemonLost);
             }
         }
     }
 
-    async fn handle_event<const N: usize>(
-        _socket_handle: &mut SocketHandle<N>,
-        broadcast_sender: &broadcast::Sender<Broadcast>,
-        event_msg: Event,
+    async fn handle_keep_alive(
+        socket_handle: &mut SocketHandle,
+        broadcast_sender: &broadcast::Sender<BroadcastMessage>,
+        keep_alive: &mut KeepAlive,
     ) -> Result {
-        match event_msg {
-            Event::ApStaConnected(mac) => {
-                if let Err(e) = broadcast_sender.send(Broadcast::Connected(mac)) {
-                    warn!("error broadcasting: {e}");
-                }
-            }
-            Event::ApStaDisconnected(mac) => {
-                if let Err(e) = broadcast_sender.send(Broadcast::Disconnected(mac)) {
-                    warn!("error broadcasting: {e}");
-                }
-            }
-            Event::Unknown(msg) => {
-                if let Err(e) = broadcast_sender.send(Broadcast::UnknownEvent(msg)) {
-                    warn!("error broadcasting: {e}");
-                }
+        let health = match socket_handle.ping().await {
+            Ok(_) => keep_alive.record(true),
+            // the socket failing means hostapd is gone, reconnect
+            Err(error::Error::Io(e)) => return Err(error::Error::UnsolicitedIoError(e)),
+            Err(e) => {
+                warn!("hostapd did not answer keep-alive ping: {e}");
+                keep_alive.record(false)
             }
         };
+        let broadcast = match health {
+            Some(Health::Unresponsive) => Broadcast::Unresponsive,
+            Some(Health::Recovered) => Broadcast::Recovered,
+            None => return Ok(()),
+        };
+        if let Err(e) = broadcast_sender.send(broadcast.into()) {
+            warn!("error broadcasting: {e}");
+        }
         Ok(())
     }
 
-    async fn handle_request<const N: usize>(
-        socket_handle: &mut SocketHandle<N>,
+    async fn handle_event(
+        _socket_handle: &mut SocketHandle,
+        broadcast_sender: &broadcast::Sender<BroadcastMessage>,
+        event_level: Level,
+        event_msg: BroadcastMessage,
+        station_removals: &mut Vec<StationRemoval>,
+    ) -> Result {
+        if let Broadcast::Disconnected(mac) = &event_msg.broadcast {
+            let (removed, pending) = std::mem::take(station_removals)
+                .into_iter()
+                .partition(|removal| removal.mac == *mac);
+            *station_removals = pending;
+            for removal in removed {
+                removal.send(Ok(()));
+            }
+        }
+        if event_msg.level.is_some_and(|level| level < event_level) {
+            return Ok(());
+        }
+        if let Err(e) = broadcast_sender.send(event_msg) {
+            warn!("error broadcasting: {e}");
+        }
+        Ok(())
+    }
+
+    async fn handle_request(
+        &self,
+        socket_handle: &mut SocketHandle,
         request: Request,
+        station_removals: &mut Vec<StationRemoval>,
     ) -> Result {
         debug!("Handling request: {request:?}");
         match request {
             Request::Custom(custom, response_channel) => {
-                let _n = socket_handle.socket.send(custom.as_bytes()).await?;
-                let n = socket_handle.socket.recv(&mut socket_handle.buffer).await?;
-                let data_str = std::str::from_utf8(&socket_handle.buffer[..n])?.trim_end();
-                debug!("Custom request response: {data_str}");
-                if response_channel.send(Ok(data_str.into())).is_err() {
+                let response = socket_handle.request(custom.as_bytes()).await;
+                debug!("Custom request response: {response:?}");
+                if response_channel.send(response).is_err() {
                     error!("Custom request response channel closed before response sent");
                 }
             }
             Request::Status(response_channel) => {
-                let _n = socket_handle.socket.send(b"STATUS").await?;
-                let n = socket_handle.socket.recv(&mut socket_handle.buffer).await?;
-                let data_str = std::str::from_utf8(&socket_handle.buffer[..n])?.trim_end();
-                let status = Status::from_response(data_str)?;
-
-                if response_channel.send(Ok(status)).is_err() {
+                let status = async {
+                    let data_str = socket_handle.request(b"STATUS").await?;
+                    Status::from_response(&data_str)
+                }
+                .await;
+                if response_channel.send(status).is_err() {
                     error!("Status request response channel closed before response sent");
                 }
             }
             Request::Config(response_channel) => {
-                let _n = socket_handle.socket.send(b"GET_CONFIG").await?;
-                let n = socket_handle.socket.recv(&mut socket_handle.buffer).await?;
-                let data_str = std::str::from_utf8(&socket_handle.buffer[..n])?.trim_end();
-                let config = Config::from_response(data_str)?;
-
-                if response_channel.send(Ok(config)).is_err() {
+                let config = async {
+                    let data_str = socket_handle.request(b"GET_CONFIG").await?;
+                    Config::from_response(&data_str)
+                }
+                .await;
+                if response_channel.send(config).is_err() {
                     error!("Config request response channel closed before response sent");
                 }
             }
@@ -160,28 +265,281 @@ impl WifiAp {
                 Self::ok_fail_request(socket_handle, request_string.as_bytes(), response_channel)
                     .await?
             }
+            Request::ListStations(response_channel) => {
+                let stations = Self::list_stations(socket_handle).await;
+                if response_channel.send(stations).is_err() {
+                    error!("List stations request response channel closed before response sent");
+                }
+            }
+            Request::GetStation(mac, response_channel) => {
+                let station = Self::get_station(socket_handle, mac).await;
+                if response_channel.send(station).is_err() {
+                    error!("Get station request response channel closed before response sent");
+                }
+            }
+            Request::RemoveStation(removal, response_channel) => {
+                let mac = removal.mac();
+                let cmd = match removal {
+                    RemoveStation::Deauthenticate(mac, reason) => {
+                        format!("DEAUTHENTICATE {mac} reason={reason}")
+                    }
+                    RemoveStation::Disassociate(mac, reason) => {
+                        format!("DISASSOCIATE {mac} reason={reason}")
+                    }
+                };
+                if let Err(e) = Self::get_station(socket_handle, mac).await {
+                    let _ = response_channel.send(Err(e));
+                    return Ok(());
+                }
+                debug!("hostapd \"{cmd}\"");
+                match socket_handle.request(cmd.as_bytes()).await {
+                    Ok(data_str) if data_str == "OK" => {
+                        station_removals.push(StationRemoval::new(
+                            self.self_sender.clone(),
+                            mac,
+                            response_channel,
+                            self.disconnect_timeout,
+                        ));
+                    }
+                    Ok(data_str) => {
+                        warn!("Error while removing station {mac}: {data_str}");
+                        let _ = response_channel.send(Err(error::Error::StationRemovalFailed(mac)));
+                    }
+                    Err(e) => {
+                        let _ = response_channel.send(Err(e));
+                    }
+                }
+            }
+            Request::RemoveStationTimeout(mac) => {
+                // removals share the same timeout, so the oldest one for this station expired
+                if let Some(index) = station_removals
+                    .iter()
+                    .position(|removal| removal.mac == mac)
+                {
+                    station_removals
+                        .remove(index)
+                        .send(Err(error::Error::Timeout));
+                }
+            }
+            Request::Acl(acl, command, response_channel) => {
+                let cmd = match command {
+                    AclCommand::Add(entry) => acl_add_command(acl, &entry),
+                    AclCommand::Remove(mac) => format!("{acl} DEL_MAC {mac}"),
+                    AclCommand::Clear => format!("{acl} CLEAR"),
+                };
+                Self::ok_fail_request(socket_handle, cmd.as_bytes(), response_channel).await?
+            }
+            Request::AclShow(acl, response_channel) => {
+                let entries = Self::acl_show(socket_handle, acl).await;
+                if response_channel.send(entries).is_err() {
+                    error!("ACL show request response channel closed before response sent");
+                }
+            }
+            Request::AclReplace(acl, entries, response_channel) => {
+                let response = Self::acl_replace(socket_handle, acl, entries).await;
+                if response_channel.send(response).is_err() {
+                    error!("ACL replace request response channel closed before response sent");
+                }
+            }
+            Request::WpsPbc(response_channel) => {
+                Self::ok_fail_request(socket_handle, b"WPS_PBC", response_channel).await?
+            }
+            Request::WpsPin(wps_pin, response_channel) => {
+                let WpsPin { uuid, pin, timeout } = wps_pin;
+                let mut cmd = format!("WPS_PIN {} {pin}", uuid.as_deref().unwrap_or("any"));
+                if let Some(timeout) = timeout {
+                    cmd.push_str(&format!(" {}", timeout.as_secs()));
+                }
+                Self::ok_fail_request(socket_handle, cmd.as_bytes(), response_channel).await?
+            }
+            Request::WpsApPin(action, response_channel) => {
+                let cmd = match &action {
+                    WpsApPin::Random(None) => "WPS_AP_PIN random".to_string(),
+                    WpsApPin::Random(Some(timeout)) => {
+                        format!("WPS_AP_PIN random {}", timeout.as_secs())
+                    }
+                    WpsApPin::Set(pin, None) => format!("WPS_AP_PIN set {pin}"),
+                    WpsApPin::Set(pin, Some(timeout)) => {
+                        format!("WPS_AP_PIN set {pin} {}", timeout.as_secs())
+                    }
+                    WpsApPin::Disable => "WPS_AP_PIN disable".to_string(),
+                    WpsApPin::Get => "WPS_AP_PIN get".to_string(),
+                };
+                let response = match socket_handle.request(cmd.as_bytes()).await {
+                    Ok(data_str) if data_str.starts_with("FAIL") => {
+                        Err(error::Error::UnexpectedWifiApRepsonse(data_str))
+                    }
+                    Ok(_) if matches!(action, WpsApPin::Disable) => Ok(None),
+                    Ok(data_str) => Ok(Some(data_str)),
+                    Err(e) => Err(e),
+                };
+                if response_channel.send(response).is_err() {
+                    error!("WPS AP PIN request response channel closed before response sent");
+                }
+            }
+            Request::WpsCancel(response_channel) => {
+                Self::ok_fail_request(socket_handle, b"WPS_CANCEL", response_channel).await?
+            }
+            Request::WpsStatus(response_channel) => {
+                let status = async {
+                    let data_str = socket_handle.request(b"WPS_GET_STATUS").await?;
+                    WpsStatus::from_response(&data_str)
+                }
+                .await;
+                if response_channel.send(status).is_err() {
+                    error!("WPS status request response channel closed before response sent");
+                }
+            }
+            Request::Ping(response_channel) => {
+                let latency = socket_handle.ping().await;
+                if response_channel.send(latency).is_err() {
+                    error!("Ping request response channel closed before response sent");
+                }
+            }
+            Request::SetLogLevel(level, response_channel) => {
+                let cmd = format!("LOG_LEVEL {level}");
+                Self::ok_fail_request(socket_handle, cmd.as_bytes(), response_channel).await?
+            }
             Request::Shutdown => (), //shutdown is handled at the scope above
+            Request::WithTimeout(..) => (), //timeouts are applied at the scope above
         }
         Ok(())
     }
 
-    async fn ok_fail_request<const N: usize>(
-        socket_handle: &mut SocketHandle<N>,
+    async fn get_station(socket_handle: &mut SocketHandle, mac: MacAddr) -> Result<StationInfo> {
+        let data_str = socket_handle
+            .request(format!("STA {mac}").as_bytes())
+            .await?;
+        if data_str.is_empty() || data_str == "FAIL" {
+            Err(error::Error::StationNotFound(mac))
+        } else {
+            StationInfo::from_response(&data_str)
+        }
+    }
+
+    async fn list_stations(socket_handle: &mut SocketHandle) -> Result<Vec<StationInfo>> {
+        let mut stations = Vec::new();
+        let mut data_str = socket_handle.request(b"STA-FIRST").await?;
+        // hostapd answers with an empty response once there are no more stations
+        while !data_str.is_empty() && data_str != "FAIL" {
+            let station = StationInfo::from_response(&data_str)?;
+            let cmd = format!("STA-NEXT {}", station.mac);
+            stations.push(station);
+            data_str = socket_handle.request(cmd.as_bytes()).await?;
+        }
+        Ok(stations)
+    }
+
+    async fn ok_fail_request(
+        socket_handle: &mut SocketHandle,
         request: &[u8],
         response_channel: oneshot::Sender<Result>,
     ) -> Result {
-        let _n = socket_handle.socket.send(request).await?;
-        let n = socket_handle.socket.recv(&mut socket_handle.buffer).await?;
-        let data_str = std::str::from_utf8(&socket_handle.buffer[..n])?.trim_end();
-        let response = if data_str == "OK" {
-            Ok(())
-        } else {
-            Err(error::Error::UnexpectedWifiApRepsonse(data_str.into()))
-        };
-
+        let response = socket_handle.command(request).await;
         if response_channel.send(response).is_err() {
             error!("Config request response channel closed before response sent");
         }
         Ok(())
     }
+
+    async fn acl_show(socket_handle: &mut SocketHandle, acl: Acl) -> Result<Vec<AclEntry>> {
+        let data_str = socket_handle
+            .request(format!("{acl} SHOW").as_bytes())
+            .await?;
+        AclEntry::vec_from_str(&data_str)
+    }
+
+    /// Replaces the entries of the list. hostapd has no way to do so atomically, so if a command
+    /// fails partway through, the entries listed beforehand are restored on a best effort basis.
+    async fn acl_replace(
+        socket_handle: &mut SocketHandle,
+        acl: Acl,
+        entries: Vec<AclEntry>,
+    ) -> Result {
+        let snapshot = Self::acl_show(socket_handle, acl).await?;
+        let Err(e) = Self::acl_update(socket_handle, acl, &snapshot, &entries).await else {
+            return Ok(());
+        };
+        warn!("Failed to replace {acl} entries, restoring them: {e}");
+        let restored = match Self::acl_show(socket_handle, acl).await {
+            Ok(current) => Self::acl_update(socket_handle, acl, &current, &snapshot).await,
+            Err(restore_error) => Err(restore_error),
+        };
+        if let Err(restore_error) = restored {
+            error!("Failed to restore {acl} entries: {restore_error}");
+        }
+        Err(e)
+    }
+
+    /// Turns the `current` entries of the list into `entries`, adding new MACs before removing
+    /// stale ones so that the list never transiently lacks a MAC present in both.
+    async fn acl_update(
+        socket_handle: &mut SocketHandle,
+        acl: Acl,
+        current: &[AclEntry],
+        entries: &[AclEntry],
+    ) -> Result {
+        let current_entry = |entry: &AclEntry| current.iter().find(|old| old.mac == entry.mac);
+        for entry in entries {
+            match current_entry(entry) {
+                None => {
+                    socket_handle
+                        .command(acl_add_command(acl, entry).as_bytes())
+                        .await?
+                }
+                // remove the MAC first, so that it is not left with its old VLAN ID
+                Some(old) if old.vlan_id != entry.vlan_id => {
+                    let cmd = format!("{acl} DEL_MAC {}", entry.mac);
+                    socket_handle.command(cmd.as_bytes()).await?;
+                    socket_handle
+                        .command(acl_add_command(acl, entry).as_bytes())
+                        .await?;
+                }
+                Some(_) => (),
+            }
+        }
+        for entry in current {
+            if !entries.iter().any(|new| new.mac == entry.mac) {
+                let cmd = format!("{acl} DEL_MAC {}", entry.mac);
+                socket_handle.command(cmd.as_bytes()).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn acl_add_command(acl: Acl, entry: &AclEntry) -> String {
+    match entry.vlan_id {
+        Some(vlan_id) => format!("{acl} ADD_MAC {} VLAN_ID={vlan_id}", entry.mac),
+        None => format!("{acl} ADD_MAC {}", entry.mac),
+    }
+}
+
+struct StationRemoval {
+    mac: MacAddr,
+    response: oneshot::Sender<Result>,
+    timeout: tokio::task::JoinHandle<()>,
+}
+
+impl StationRemoval {
+    fn new(
+        sender: mpsc::Sender<Request>,
+        mac: MacAddr,
+        response: oneshot::Sender<Result>,
+        timeout: Duration,
+    ) -> Self {
+        Self {
+            mac,
+            response,
+            timeout: tokio::task::spawn(async move {
+                tokio::time::sleep(timeout).await;
+                let _ = sender.send(Request::RemoveStationTimeout(mac)).await;
+            }),
+        }
+    }
+
+    fn send(self, result: Result) {
+        self.timeout.abort();
+        let _ = self.response.send(result);
+    }
 }
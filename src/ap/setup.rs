@@ -1,4 +1,5 @@
 use super::*;
+use crate::socket_handle::DEFAULT_MAX_RESPONSE_SIZE;
 
 /// A convenient default type for setting up the WiFiAp process.
 pub type WifiSetup = WifiSetupGeneric<32, 32>;
@@ -27,9 +28,15 @@ impl<const C: usize, const B: usize> WifiSetupGeneric<C, B> {
             wifi: WifiAp {
                 socket_path: PATH_DEFAULT_SERVER.into(),
                 attach_options: vec![],
+                event_level: Level::Debug,
                 request_receiver,
                 broadcast_sender,
                 self_sender,
+                disconnect_timeout: Duration::from_secs(5),
+                request_timeout: Duration::from_secs(5),
+                max_response_size: DEFAULT_MAX_RESPONSE_SIZE,
+                ping_interval: None,
+                ping_failure_threshold: 3,
             },
             request_client,
             broadcast_receiver,
@@ -46,6 +53,40 @@ impl<const C: usize, const B: usize> WifiSetupGeneric<C, B> {
         }
     }
 
+    /// Minimum level of the hostapd events to broadcast. Defaults to `Level::Debug`.
+    pub fn set_event_level(&mut self, level: Level) {
+        self.wifi.event_level = level;
+    }
+
+    /// Deauthenticating or disassociating a station resolves once hostapd reports it as
+    /// disconnected. This timeout applies if no such event is received.
+    pub fn set_disconnect_timeout(&mut self, timeout: Duration) {
+        self.wifi.disconnect_timeout = timeout;
+    }
+
+    /// How long to wait for hostapd to respond to a request before it fails with
+    /// `Error::Timeout`. Individual clients may override it with `RequestClient::with_timeout`.
+    pub fn set_request_timeout(&mut self, timeout: Duration) {
+        self.wifi.request_timeout = timeout;
+    }
+
+    /// Responses from hostapd larger than this fail with `Error::ResponseTruncated` instead of
+    /// being parsed. Defaults to 64 KiB.
+    pub fn set_max_response_size(&mut self, size: usize) {
+        self.wifi.max_response_size = size;
+    }
+
+    /// Enables the health monitor, which pings hostapd at this interval and broadcasts
+    /// `Broadcast::Unresponsive` once it fails to answer too many consecutive pings.
+    pub fn set_ping_interval(&mut self, interval: Duration) {
+        self.wifi.ping_interval = Some(interval);
+    }
+
+    /// Consecutive ping failures after which hostapd is reported as unresponsive. Defaults to 3.
+    pub fn set_ping_failure_threshold(&mut self, threshold: u32) {
+        self.wifi.ping_failure_threshold = threshold;
+    }
+
     pub fn get_broadcast_receiver(&self) -> BroadcastReceiver {
         self.wifi.broadcast_sender.subscribe()
     }
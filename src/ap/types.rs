@@ -1,94 +1,722 @@
-use super::{error, Result};
+use super::{error, warn, MacAddr, Result};
+use crate::parsing::{event_field, take_parsed, take_parsed_with};
+use crate::{ChannelWidth, Frequency};
 use serde::{de, Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
 
-/// Status of the WiFi Station
-#[derive(Serialize, Deserialize, Debug)]
+/// Status of the WiFi Access Point. Fields which are only reported by some hostapd versions or
+/// configurations are optional.
+#[derive(Serialize, Debug)]
 pub struct Status {
     pub state: String,
     pub phy: String,
-    pub freq: String,
-    pub num_sta_non_erp: String,
-    pub num_sta_no_short_slot_time: String,
-    pub num_sta_no_short_preamble: String,
-    pub olbc: String,
-    pub num_sta_ht_no_gf: String,
-    pub num_sta_no_ht: String,
-    pub num_sta_ht_20_mhz: String,
-    pub num_sta_ht40_intolerant: String,
-    pub olbc_ht: String,
-    pub ht_op_mode: String,
-    pub cac_time_seconds: String,
-    pub cac_time_left_seconds: String,
-    pub channel: String,
-    pub secondary_channel: String,
-    pub ieee80211n: String,
-    pub ieee80211ac: String,
-    pub ieee80211ax: String,
-    pub beacon_int: String,
-    pub dtim_period: String,
-    pub ht_caps_info: String,
-    pub ht_mcs_bitmask: String,
-    pub supported_rates: String,
-    pub max_txpower: String,
-    pub bss: Vec<String>,
-    pub bssid: Vec<String>,
-    pub ssid: Vec<String>,
-    pub num_sta: Vec<String>,
+    pub freq: Frequency,
+    /// Width of the operating channel, from the HE, VHT or HT configuration
+    pub channel_width: Option<ChannelWidth>,
+    pub num_sta_non_erp: Option<u32>,
+    pub num_sta_no_short_slot_time: Option<u32>,
+    pub num_sta_no_short_preamble: Option<u32>,
+    pub olbc: Option<u32>,
+    pub num_sta_ht_no_gf: Option<u32>,
+    pub num_sta_no_ht: Option<u32>,
+    pub num_sta_ht_20_mhz: Option<u32>,
+    pub num_sta_ht40_intolerant: Option<u32>,
+    pub olbc_ht: Option<u32>,
+    pub ht_op_mode: Option<String>,
+    pub cac_time_seconds: Option<u32>,
+    /// Either a number of seconds or `N/A` when no channel availability check is running
+    pub cac_time_left_seconds: Option<String>,
+    pub channel: Option<u8>,
+    pub secondary_channel: Option<i8>,
+    pub ieee80211n: Option<bool>,
+    pub ieee80211ac: Option<bool>,
+    pub ieee80211ax: Option<bool>,
+    pub beacon_int: Option<u16>,
+    pub dtim_period: Option<u8>,
+    pub ht_caps_info: Option<String>,
+    pub ht_mcs_bitmask: Option<String>,
+    pub supported_rates: Option<String>,
+    pub max_txpower: Option<u32>,
+    pub bss: Vec<BssStatus>,
+}
+
+/// Status of one of the BSSes (ie: one SSID) served by the Access Point
+#[derive(Serialize, Debug)]
+pub struct BssStatus {
+    /// Name of the interface of the BSS
+    pub name: String,
+    pub bssid: MacAddr,
+    pub ssid: String,
+    pub num_sta: u32,
+}
+
+/// Raw status as reported by hostapd, where per-BSS fields are parallel `bss[i]` arrays.
+#[derive(Deserialize)]
+struct RawStatus {
+    state: String,
+    phy: String,
+    freq: Frequency,
+    num_sta_non_erp: Option<u32>,
+    num_sta_no_short_slot_time: Option<u32>,
+    num_sta_no_short_preamble: Option<u32>,
+    olbc: Option<u32>,
+    num_sta_ht_no_gf: Option<u32>,
+    num_sta_no_ht: Option<u32>,
+    num_sta_ht_20_mhz: Option<u32>,
+    num_sta_ht40_intolerant: Option<u32>,
+    olbc_ht: Option<u32>,
+    ht_op_mode: Option<String>,
+    cac_time_seconds: Option<u32>,
+    cac_time_left_seconds: Option<String>,
+    channel: Option<u8>,
+    secondary_channel: Option<i8>,
+    ieee80211n: Option<bool>,
+    ieee80211ac: Option<bool>,
+    ieee80211ax: Option<bool>,
+    vht_oper_chwidth: Option<u8>,
+    he_oper_chwidth: Option<u8>,
+    beacon_int: Option<u16>,
+    dtim_period: Option<u8>,
+    ht_caps_info: Option<String>,
+    ht_mcs_bitmask: Option<String>,
+    supported_rates: Option<String>,
+    max_txpower: Option<u32>,
+    #[serde(default)]
+    bss: Vec<String>,
+    #[serde(default)]
+    bssid: Vec<String>,
+    #[serde(default)]
+    ssid: Vec<String>,
+    #[serde(default)]
+    num_sta: Vec<u32>,
 }
 
 impl Status {
     pub fn from_response(response: &str) -> Result<Status> {
         use config::{Config, File, FileFormat};
-        let config = Config::builder()
+        let map_err = |e| error::Error::ParsingWifiStatus {
+            e,
+            s: response.into(),
+        };
+        let raw = Config::builder()
             .add_source(File::from_str(response, FileFormat::Ini))
             .build()
-            .map_err(|e| error::Error::ParsingWifiStatus {
-                e,
-                s: response.into(),
-            })?;
+            .map_err(map_err)?
+            .try_deserialize::<RawStatus>()
+            .map_err(map_err)?;
+
+        let mut bss = Vec::with_capacity(raw.bss.len());
+        for (i, name) in raw.bss.into_iter().enumerate() {
+            // a BSS without a valid BSSID is left out rather than failing the whole status
+            let Some(bssid) = raw
+                .bssid
+                .get(i)
+                .and_then(|bssid| MacAddr::from_str(bssid).ok())
+            else {
+                warn!(
+                    "Missing or invalid BSSID for {name}: {:?}",
+                    raw.bssid.get(i)
+                );
+                continue;
+            };
+            bss.push(BssStatus {
+                name,
+                bssid,
+                ssid: raw.ssid.get(i).cloned().unwrap_or_default(),
+                num_sta: raw.num_sta.get(i).copied().unwrap_or_default(),
+            });
+        }
+
+        let secondary_channel = raw.secondary_channel.is_some_and(|offset| offset != 0);
+        let chwidth = if raw.ieee80211ax == Some(true) {
+            raw.he_oper_chwidth
+        } else if raw.ieee80211ac == Some(true) {
+            raw.vht_oper_chwidth
+        } else if raw.ieee80211n.is_some() {
+            Some(0)
+        } else {
+            None
+        };
+        let channel_width =
+            chwidth.and_then(|chwidth| ChannelWidth::from_oper_chwidth(chwidth, secondary_channel));
+
+        Ok(Status {
+            state: raw.state,
+            phy: raw.phy,
+            freq: raw.freq,
+            channel_width,
+            num_sta_non_erp: raw.num_sta_non_erp,
+            num_sta_no_short_slot_time: raw.num_sta_no_short_slot_time,
+            num_sta_no_short_preamble: raw.num_sta_no_short_preamble,
+            olbc: raw.olbc,
+            num_sta_ht_no_gf: raw.num_sta_ht_no_gf,
+            num_sta_no_ht: raw.num_sta_no_ht,
+            num_sta_ht_20_mhz: raw.num_sta_ht_20_mhz,
+            num_sta_ht40_intolerant: raw.num_sta_ht40_intolerant,
+            olbc_ht: raw.olbc_ht,
+            ht_op_mode: raw.ht_op_mode,
+            cac_time_seconds: raw.cac_time_seconds,
+            cac_time_left_seconds: raw.cac_time_left_seconds,
+            channel: raw.channel,
+            secondary_channel: raw.secondary_channel,
+            ieee80211n: raw.ieee80211n,
+            ieee80211ac: raw.ieee80211ac,
+            ieee80211ax: raw.ieee80211ax,
+            beacon_int: raw.beacon_int,
+            dtim_period: raw.dtim_period,
+            ht_caps_info: raw.ht_caps_info,
+            ht_mcs_bitmask: raw.ht_mcs_bitmask,
+            supported_rates: raw.supported_rates,
+            max_txpower: raw.max_txpower,
+            bss,
+        })
+    }
+}
+
+/// A station associated with the Access Point, as reported by `STA <mac>`. Fields which are not
+/// parsed into a typed field are kept in `extra`.
+#[derive(Serialize, Debug, Clone)]
+pub struct StationInfo {
+    pub mac: MacAddr,
+    /// Flags such as `AUTH`, `ASSOC`, `AUTHORIZED`, `WMM`, `HT`, `VHT` or `HE`
+    pub flags: Vec<String>,
+    pub aid: Option<u16>,
+    pub capability: Option<u16>,
+    pub listen_interval: Option<u16>,
+    /// Supported rates, in units of 500 kbps with the basic rate bit (0x80) set for basic rates
+    pub supported_rates: Vec<u8>,
+    pub rx_packets: Option<u64>,
+    pub tx_packets: Option<u64>,
+    pub rx_bytes: Option<u64>,
+    pub tx_bytes: Option<u64>,
+    /// Signal strength of the last received frame in dBm
+    pub signal: Option<i32>,
+    pub inactive_msec: Option<u64>,
+    /// Seconds since the station connected
+    pub connected_time: Option<u64>,
+    pub ht_caps_info: Option<u16>,
+    pub vht_caps_info: Option<u32>,
+    pub he_capab: Option<String>,
+    pub extra: HashMap<String, String>,
+}
+
+impl StationInfo {
+    pub fn from_response(response: &str) -> Result<StationInfo> {
+        let mut lines = response.lines();
+        let mac = MacAddr::from_str(lines.next().unwrap_or_default())?;
+        let mut fields: HashMap<String, String> = lines
+            .filter_map(|line| line.split_once('='))
+            .map(|(key, value)| (key.to_string(), value.trim().to_string()))
+            .collect();
+
+        let flags = fields
+            .remove("flags")
+            .map(|flags| {
+                flags
+                    .split(['[', ']'])
+                    .filter(|flag| !flag.is_empty())
+                    .map(|flag| flag.to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let supported_rates = fields
+            .remove("supported_rates")
+            .map(|rates| {
+                rates
+                    .split_whitespace()
+                    .filter_map(|rate| u8::from_str_radix(rate, 16).ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok(StationInfo {
+            mac,
+            flags,
+            aid: take_parsed(&mut fields, "aid"),
+            capability: take_parsed_with(&mut fields, "capability", parse_hex_u16),
+            listen_interval: take_parsed(&mut fields, "listen_interval"),
+            supported_rates,
+            rx_packets: take_parsed(&mut fields, "rx_packets"),
+            tx_packets: take_parsed(&mut fields, "tx_packets"),
+            rx_bytes: take_parsed(&mut fields, "rx_bytes"),
+            tx_bytes: take_parsed(&mut fields, "tx_bytes"),
+            signal: take_parsed(&mut fields, "signal"),
+            inactive_msec: take_parsed(&mut fields, "inactive_msec"),
+            connected_time: take_parsed(&mut fields, "connected_time"),
+            ht_caps_info: take_parsed_with(&mut fields, "ht_caps_info", parse_hex_u16),
+            vht_caps_info: take_parsed_with(&mut fields, "vht_caps_info", parse_hex_u32),
+            he_capab: fields.remove("he_capab"),
+            extra: fields,
+        })
+    }
+
+    pub fn has_flag(&self, flag: &str) -> bool {
+        self.flags.iter().any(|f| f == flag)
+    }
+}
+
+fn parse_hex_u16(s: &str) -> std::result::Result<u16, std::num::ParseIntError> {
+    u16::from_str_radix(s.trim_start_matches("0x"), 16)
+}
+
+fn parse_hex_u32(s: &str) -> std::result::Result<u32, std::num::ParseIntError> {
+    u32::from_str_radix(s.trim_start_matches("0x"), 16)
+}
+
+/// MAC based access control lists of hostapd
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Acl {
+    /// Stations allowed to connect when `macaddr_acl` requires accept list membership
+    Accept,
+    /// Stations which are never allowed to connect
+    Deny,
+}
+
+impl std::fmt::Display for Acl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Acl::Accept => write!(f, "ACCEPT_ACL"),
+            Acl::Deny => write!(f, "DENY_ACL"),
+        }
+    }
+}
+
+/// An entry of an access control list
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct AclEntry {
+    pub mac: MacAddr,
+    pub vlan_id: Option<u16>,
+}
+
+impl AclEntry {
+    pub fn new(mac: MacAddr) -> AclEntry {
+        AclEntry { mac, vlan_id: None }
+    }
 
-        Ok(config.try_deserialize::<Status>().unwrap())
+    /// Parses the `<mac> VLAN_ID=<id>` lines of `ACCEPT_ACL SHOW` or `DENY_ACL SHOW`
+    pub fn vec_from_str(response: &str) -> Result<Vec<AclEntry>> {
+        let mut entries = Vec::new();
+        for line in response.lines().filter(|line| !line.trim().is_empty()) {
+            let mut line_split = line.split_whitespace();
+            let mac = MacAddr::from_str(line_split.next().unwrap_or_default())?;
+            // a VLAN id of 0 means no VLAN is assigned
+            let vlan_id = line_split
+                .find_map(|field| field.strip_prefix("VLAN_ID="))
+                .map(u16::from_str)
+                .transpose()?
+                .filter(|vlan_id| *vlan_id != 0);
+            entries.push(AclEntry { mac, vlan_id });
+        }
+        Ok(entries)
     }
 }
 
-/// Configuration of the WiFi station
+/// Details of a DFS (radar detection) event. Fields are only set when reported by the event.
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct DfsInfo {
+    pub freq: Option<u32>,
+    pub channel: Option<u8>,
+    pub chan_width: Option<u8>,
+    /// Center frequency of the first segment
+    pub cf1: Option<u32>,
+    /// Center frequency of the second segment, for 80+80 MHz channels
+    pub cf2: Option<u32>,
+    /// Duration of the channel availability check, in seconds
+    pub cac_time: Option<u32>,
+    /// Whether the channel availability check succeeded
+    pub success: Option<bool>,
+}
+
+impl DfsInfo {
+    pub(crate) fn from_args(args: &str) -> DfsInfo {
+        let number = |key| event_field(args, key).and_then(|value| value.parse().ok());
+        DfsInfo {
+            freq: number("freq"),
+            channel: event_field(args, "chan").and_then(|chan| chan.parse().ok()),
+            chan_width: event_field(args, "chan_width")
+                .or_else(|| event_field(args, "width"))
+                .and_then(|width| width.parse().ok()),
+            cf1: number("cf1"),
+            cf2: number("cf2"),
+            cac_time: event_field(args, "cac_time")
+                .and_then(|time| time.trim_end_matches('s').parse().ok()),
+            success: event_field(args, "success").map(|success| success == "1"),
+        }
+    }
+}
+
+/// WPS state of the Access Point, as reported by `WPS_GET_STATUS`
+#[derive(Serialize, Debug, Clone)]
+pub struct WpsStatus {
+    /// `Active`, `Disabled`, `Timed-out` or `Overlap`
+    pub pbc_status: String,
+    /// `None`, `Success` or `Failed`
+    pub last_result: String,
+    pub failure_reason: Option<String>,
+    pub peer_address: Option<MacAddr>,
+}
+
+impl WpsStatus {
+    pub fn from_response(response: &str) -> Result<WpsStatus> {
+        let mut fields: HashMap<&str, &str> = response
+            .lines()
+            .filter_map(|line| line.split_once(": "))
+            .collect();
+        let pbc_status = fields
+            .remove("PBC Status")
+            .ok_or_else(|| error::Error::UnexpectedWifiApRepsonse(response.into()))?;
+        Ok(WpsStatus {
+            pbc_status: pbc_status.to_string(),
+            last_result: fields
+                .remove("Last WPS result")
+                .unwrap_or("None")
+                .to_string(),
+            failure_reason: fields.remove("Failure Reason").map(str::to_string),
+            peer_address: fields
+                .remove("Peer Address")
+                .map(MacAddr::from_str)
+                .transpose()?,
+        })
+    }
+}
+
+/// Configuration of the WiFi Access Point. Security fields are only reported when WPA is enabled.
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Config {
     pub bssid: String,
     pub ssid: String,
-    #[serde(deserialize_with = "deserialize_enabled_bool")]
-    pub wps_state: bool,
-    pub wpa: i32,
-    pub ket_mgmt: String,
-    pub group_cipher: String,
-    pub rsn_pairwise_cipher: String,
-    pub wpa_pairwise_cipher: String,
+    /// Whether WPS is enabled. Not reported if hostapd is built without WPS support.
+    #[serde(default, deserialize_with = "deserialize_wps_state")]
+    pub wps_state: Option<bool>,
+    pub wpa: Option<i32>,
+    pub key_mgmt: Option<String>,
+    pub group_cipher: Option<String>,
+    pub rsn_pairwise_cipher: Option<String>,
+    pub wpa_pairwise_cipher: Option<String>,
 }
 
 impl Config {
     pub fn from_response(response: &str) -> Result<Config> {
         use config::{File, FileFormat};
-        let config = config::Config::builder()
+        let map_err = |e| error::Error::ParsingWifiConfig {
+            e,
+            s: response.into(),
+        };
+        config::Config::builder()
             .add_source(File::from_str(response, FileFormat::Ini))
             .build()
-            .map_err(|e| error::Error::ParsingWifiConfig {
-                e,
-                s: response.into(),
-            })?;
-
-        Ok(config.try_deserialize::<Config>().unwrap())
+            .map_err(map_err)?
+            .try_deserialize::<Config>()
+            .map_err(map_err)
     }
 }
 
-fn deserialize_enabled_bool<'de, D>(deserializer: D) -> std::result::Result<bool, D::Error>
+fn deserialize_wps_state<'de, D>(deserializer: D) -> std::result::Result<Option<bool>, D::Error>
 where
     D: de::Deserializer<'de>,
 {
-    let s: &str = de::Deserialize::deserialize(deserializer)?;
+    let s: String = de::Deserialize::deserialize(deserializer)?;
+
+    match s.as_str() {
+        "configured" | "not configured" | "enabled" => Ok(Some(true)),
+        "disabled" => Ok(Some(false)),
+        _ => Err(de::Error::unknown_variant(
+            &s,
+            &["configured", "not configured", "disabled"],
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mac(s: &str) -> MacAddr {
+        MacAddr::from_str(s).unwrap()
+    }
+
+    // `STATUS` of hostapd 2.10 serving two BSSes
+    const STATUS_RESPONSE: &str = "state=ENABLED
+phy=phy0
+freq=2437
+num_sta_non_erp=0
+num_sta_no_short_slot_time=0
+num_sta_no_short_preamble=0
+olbc=0
+num_sta_ht_no_gf=1
+num_sta_no_ht=0
+num_sta_ht_20_mhz=1
+num_sta_ht40_intolerant=0
+olbc_ht=0
+ht_op_mode=0x0
+cac_time_seconds=0
+cac_time_left_seconds=N/A
+channel=6
+edmg_enable=0
+edmg_channel=0
+secondary_channel=0
+ieee80211n=1
+ieee80211ac=0
+ieee80211ax=0
+beacon_int=100
+dtim_period=2
+ht_caps_info=000c
+ht_mcs_bitmask=ffff0000000000000000
+supported_rates=02 04 0b 16 0c 12 18 24 30 48 60 6c
+max_txpower=20
+bss[0]=wlan0
+bssid[0]=02:00:00:00:03:00
+ssid[0]=test-ap
+num_sta[0]=1
+bss[1]=wlan0-1
+bssid[1]=02:00:00:00:03:01
+ssid[1]=guest
+num_sta[1]=0
+";
+
+    // `STATUS` of hostapd 2.6, which predates the `ieee80211ax` and `cac_*` fields
+    const STATUS_RESPONSE_2_6: &str = "state=ENABLED
+phy=phy1
+freq=5180
+num_sta_non_erp=0
+num_sta_no_short_slot_time=0
+num_sta_no_short_preamble=0
+olbc=0
+num_sta_ht_no_gf=0
+num_sta_no_ht=0
+num_sta_ht_20_mhz=0
+num_sta_ht40_intolerant=0
+olbc_ht=0
+ht_op_mode=0x4
+channel=36
+secondary_channel=1
+ieee80211n=1
+ieee80211ac=1
+beacon_int=100
+dtim_period=2
+vht_oper_chwidth=1
+vht_oper_centr_freq_seg0_idx=42
+vht_oper_centr_freq_seg1_idx=0
+vht_caps_info=338001b2
+bss[0]=wlan1
+bssid[0]=02:00:00:00:04:00
+ssid[0]=test-ap-5g
+num_sta[0]=0
+";
+
+    #[test]
+    fn parses_status() {
+        let status = Status::from_response(STATUS_RESPONSE).unwrap();
+        assert_eq!(status.state, "ENABLED");
+        assert_eq!(status.phy, "phy0");
+        assert_eq!(status.freq, Frequency(2437));
+        assert_eq!(status.channel, Some(6));
+        assert_eq!(status.channel_width, Some(ChannelWidth::Mhz20));
+        assert_eq!(status.num_sta_ht_no_gf, Some(1));
+        assert_eq!(status.ht_op_mode.as_deref(), Some("0x0"));
+        assert_eq!(status.cac_time_seconds, Some(0));
+        assert_eq!(status.cac_time_left_seconds.as_deref(), Some("N/A"));
+        assert_eq!(status.ieee80211n, Some(true));
+        assert_eq!(status.ieee80211ac, Some(false));
+        assert_eq!(status.ieee80211ax, Some(false));
+        assert_eq!(status.beacon_int, Some(100));
+        assert_eq!(status.dtim_period, Some(2));
+        assert_eq!(status.max_txpower, Some(20));
+        assert_eq!(status.bss.len(), 2);
+        assert_eq!(status.bss[0].name, "wlan0");
+        assert_eq!(status.bss[0].bssid, mac("02:00:00:00:03:00"));
+        assert_eq!(status.bss[0].ssid, "test-ap");
+        assert_eq!(status.bss[0].num_sta, 1);
+        assert_eq!(status.bss[1].name, "wlan0-1");
+        assert_eq!(status.bss[1].bssid, mac("02:00:00:00:03:01"));
+        assert_eq!(status.bss[1].ssid, "guest");
+        assert_eq!(status.bss[1].num_sta, 0);
+    }
+
+    #[test]
+    fn parses_status_without_recent_fields() {
+        let status = Status::from_response(STATUS_RESPONSE_2_6).unwrap();
+        assert_eq!(status.freq, Frequency(5180));
+        assert_eq!(status.secondary_channel, Some(1));
+        assert_eq!(status.ieee80211ac, Some(true));
+        assert_eq!(status.ieee80211ax, None);
+        assert_eq!(status.cac_time_seconds, None);
+        assert_eq!(status.cac_time_left_seconds, None);
+        assert_eq!(status.supported_rates, None);
+        assert_eq!(status.channel_width, Some(ChannelWidth::Mhz80));
+        assert_eq!(status.bss.len(), 1);
+        assert_eq!(status.bss[0].ssid, "test-ap-5g");
+    }
+
+    #[test]
+    fn skips_bss_without_valid_bssid() {
+        let response = STATUS_RESPONSE.replace("bssid[0]=02:00:00:00:03:00", "bssid[0]=invalid");
+        let status = Status::from_response(&response).unwrap();
+        assert_eq!(status.bss.len(), 1);
+        assert_eq!(status.bss[0].name, "wlan0-1");
+        assert_eq!(status.bss[0].bssid, mac("02:00:00:00:03:01"));
+
+        let response = STATUS_RESPONSE_2_6.replace("bssid[0]=02:00:00:00:04:00\n", "");
+        let status = Status::from_response(&response).unwrap();
+        assert!(status.bss.is_empty());
+    }
+
+    #[test]
+    fn parses_station_info() {
+        // `STA <mac>` of hostapd 2.10 for a WPA2 station
+        let response = "02:00:00:00:01:00
+flags=[AUTH][ASSOC][AUTHORIZED][WMM][HT][VHT]
+aid=1
+capability=0x0431
+listen_interval=10
+supported_rates=82 84 8b 96 0c 12 18 24 30 48 60 6c
+timeout_next=NULLFUNC POLL
+dot11RSNAStatsSTAAddress=02:00:00:00:01:00
+dot11RSNAStatsSelectedPairwiseCipher=00-0f-ac-4
+wpa=2
+AKMSuiteSelector=00-0f-ac-2
+rx_packets=120
+tx_packets=80
+rx_bytes=15000
+tx_bytes=9000
+inactive_msec=300
+signal=-42
+rx_rate_info=650 mcs 7 shortGI
+tx_rate_info=650 mcs 7 shortGI
+connected_time=35
+ht_mcs_bitmask=ffff0000000000000000
+ht_caps_info=0x016e
+vht_caps_info=0x0f8259b2
+";
+        let station = StationInfo::from_response(response).unwrap();
+        assert_eq!(station.mac, mac("02:00:00:00:01:00"));
+        assert_eq!(
+            station.flags,
+            ["AUTH", "ASSOC", "AUTHORIZED", "WMM", "HT", "VHT"]
+        );
+        assert!(station.has_flag("AUTHORIZED"));
+        assert!(!station.has_flag("HE"));
+        assert_eq!(station.aid, Some(1));
+        assert_eq!(station.capability, Some(0x0431));
+        assert_eq!(station.listen_interval, Some(10));
+        assert_eq!(
+            station.supported_rates,
+            [0x82, 0x84, 0x8b, 0x96, 0x0c, 0x12, 0x18, 0x24, 0x30, 0x48, 0x60, 0x6c]
+        );
+        assert_eq!(station.rx_packets, Some(120));
+        assert_eq!(station.tx_packets, Some(80));
+        assert_eq!(station.rx_bytes, Some(15000));
+        assert_eq!(station.tx_bytes, Some(9000));
+        assert_eq!(station.signal, Some(-42));
+        assert_eq!(station.inactive_msec, Some(300));
+        assert_eq!(station.connected_time, Some(35));
+        assert_eq!(station.ht_caps_info, Some(0x016e));
+        assert_eq!(station.vht_caps_info, Some(0x0f82_59b2));
+        assert_eq!(station.he_capab, None);
+        assert_eq!(station.extra["wpa"], "2");
+        assert_eq!(station.extra["rx_rate_info"], "650 mcs 7 shortGI");
+        assert_eq!(station.extra["timeout_next"], "NULLFUNC POLL");
+        assert!(!station.extra.contains_key("aid"));
+    }
+
+    #[test]
+    fn keeps_invalid_station_fields() {
+        let response = "02:00:00:00:01:00\nflags=[AUTH]\naid=none\nvht_caps_info=0xzz\n";
+        let station = StationInfo::from_response(response).unwrap();
+        assert_eq!(station.flags, ["AUTH"]);
+        assert_eq!(station.aid, None);
+        assert_eq!(station.vht_caps_info, None);
+        assert_eq!(station.extra["aid"], "none");
+        assert_eq!(station.extra["vht_caps_info"], "0xzz");
+        assert!(station.supported_rates.is_empty());
+
+        assert!(StationInfo::from_response("FAIL").is_err());
+    }
+
+    #[test]
+    fn parses_acl_entries() {
+        // `ACCEPT_ACL SHOW` of hostapd 2.10
+        let response = "02:00:00:00:01:00 VLAN_ID=0\n02:00:00:00:02:00 VLAN_ID=10\n";
+        assert_eq!(
+            AclEntry::vec_from_str(response).unwrap(),
+            [
+                AclEntry::new(mac("02:00:00:00:01:00")),
+                AclEntry {
+                    mac: mac("02:00:00:00:02:00"),
+                    vlan_id: Some(10),
+                },
+            ]
+        );
+        // the VLAN ID is optional
+        assert_eq!(
+            AclEntry::vec_from_str("02:00:00:00:01:00\n").unwrap(),
+            [AclEntry::new(mac("02:00:00:00:01:00"))]
+        );
+        assert!(AclEntry::vec_from_str("").unwrap().is_empty());
+        assert!(AclEntry::vec_from_str("02:00:00:00:01:00 VLAN_ID=x").is_err());
+        assert!(AclEntry::vec_from_str("not-a-mac VLAN_ID=0").is_err());
+    }
+
+    #[test]
+    fn parses_wps_status() {
+        // `WPS_GET_STATUS` of hostapd 2.10
+        let status =
+            WpsStatus::from_response("PBC Status: Disabled\nLast WPS result: None\n").unwrap();
+        assert_eq!(status.pbc_status, "Disabled");
+        assert_eq!(status.last_result, "None");
+        assert_eq!(status.failure_reason, None);
+        assert_eq!(status.peer_address, None);
+
+        let response = "PBC Status: Active
+Last WPS result: Success
+Peer Address: 02:00:00:00:01:00
+";
+        let status = WpsStatus::from_response(response).unwrap();
+        assert_eq!(status.pbc_status, "Active");
+        assert_eq!(status.last_result, "Success");
+        assert_eq!(status.failure_reason, None);
+        assert_eq!(status.peer_address, Some(mac("02:00:00:00:01:00")));
+
+        let response = "PBC Status: Timed-out
+Last WPS result: Failed
+Failure Reason: Invalid PIN
+Peer Address: 02:00:00:00:01:00
+";
+        let status = WpsStatus::from_response(response).unwrap();
+        assert_eq!(status.pbc_status, "Timed-out");
+        assert_eq!(status.last_result, "Failed");
+        assert_eq!(status.failure_reason.as_deref(), Some("Invalid PIN"));
+        assert_eq!(status.peer_address, Some(mac("02:00:00:00:01:00")));
+
+        assert!(WpsStatus::from_response("FAIL").is_err());
+    }
+
+    #[test]
+    fn parses_config() {
+        // `GET_CONFIG` of a WPA2/WPA3-Personal transition network
+        let response = "bssid=02:00:00:00:03:00
+ssid=test-ap
+wps_state=disabled
+wpa=2
+key_mgmt=WPA-PSK SAE
+group_cipher=CCMP
+rsn_pairwise_cipher=CCMP
+";
+        let config = Config::from_response(response).unwrap();
+        assert_eq!(config.bssid, "02:00:00:00:03:00");
+        assert_eq!(config.ssid, "test-ap");
+        assert_eq!(config.wps_state, Some(false));
+        assert_eq!(config.wpa, Some(2));
+        assert_eq!(config.key_mgmt.as_deref(), Some("WPA-PSK SAE"));
+        assert_eq!(config.group_cipher.as_deref(), Some("CCMP"));
+        assert_eq!(config.rsn_pairwise_cipher.as_deref(), Some("CCMP"));
+        assert_eq!(config.wpa_pairwise_cipher, None);
 
-    match s {
-        "enabled" => Ok(true),
-        "disabled" => Ok(false),
-        _ => Err(de::Error::unknown_variant(s, &["enabled", "disabled"])),
+        // an open network, with hostapd built without WPS
+        let config = Config::from_response("bssid=02:00:00:00:03:00\nssid=open-ap\n").unwrap();
+        assert_eq!(config.ssid, "open-ap");
+        assert_eq!(config.wps_state, None);
+        assert_eq!(config.wpa, None);
+        assert_eq!(config.key_mgmt, None);
     }
 }
@@ -9,12 +9,30 @@ pub enum Error {
     StartupAborted,
     #[error("error parsing wifi status {e}: \n{s}")]
     ParsingWifiStatus { e: config::ConfigError, s: String },
+    #[error("station {0} not found")]
+    StationNotFound(MacAddr),
+    #[error("bss {0} not found")]
+    BssNotFound(MacAddr),
+    #[error("hostapd failed to remove station {0}")]
+    StationRemovalFailed(MacAddr),
+    #[error("invalid mac address: {0}")]
+    InvalidMacAddress(String),
+    #[error("invalid frequency: {0}")]
+    InvalidFrequency(String),
     #[error("error parsing wifi config {e}: \n{s}")]
     ParsingWifiConfig { e: config::ConfigError, s: String },
     #[error("unexpected wifi ap response: {0}")]
     UnexpectedWifiApRepsonse(String),
     #[error("timeout waiting for response")]
     Timeout,
+    #[error("response exceeded the maximum size of {0} bytes")]
+    ResponseTruncated(usize),
+    #[error("wpa_supplicant rejected the scan: {0}")]
+    ScanRejected(String),
+    #[error("wpa_supplicant failed to start the scan (ret={0})")]
+    ScanFailed(i32),
+    #[error("timeout waiting for scan results")]
+    ScanTimeout,
     #[error("did not write all bytes {0}/{1}")]
     DidNotWriteAllBytes(usize, usize),
     #[error("error parsing int: {0}")]
@@ -34,11 +52,13 @@ pub enum Error {
     #[error("wifi_ctrl::ap internal event channel unexpectedly closed")]
     WifiApEventChannelClosed,
     #[error("wifi ap broadcast: {0}")]
-    WifiApBroadcast(#[from] broadcast::error::SendError<ap::Broadcast>),
+    WifiApBroadcast(#[from] broadcast::error::SendError<ap::BroadcastMessage>),
     #[error("wifi::sta broadcast: {0}")]
-    WifiStaBroadcast(#[from] broadcast::error::SendError<sta::Broadcast>),
+    WifiStaBroadcast(#[from] broadcast::error::SendError<sta::BroadcastMessage>),
     #[error("timeout opening socket {0}")]
     TimeoutOpeningSocket(String),
+    #[error("lost connection to the wifi daemon")]
+    DaemonLost,
     #[error("permission denied opening socket {0}")]
     PermissionDeniedOpeningSocket(String),
 }
@@ -0,0 +1,280 @@
+use super::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A channel center frequency in MHz, as reported by hostapd and wpa_supplicant
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Frequency(pub u32);
+
+/// Frequency band of a channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum Band {
+    /// 2.4 GHz, channels 1 to 14
+    TwoGhz,
+    /// 5 GHz, channels 32 to 177
+    FiveGhz,
+    /// 6 GHz, channels 1 to 233
+    SixGhz,
+    /// 60 GHz (DMG), channels 1 to 6
+    SixtyGhz,
+}
+
+/// A channel number within a band
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub struct Channel {
+    pub band: Band,
+    pub number: u8,
+}
+
+/// Width of the channel used by a BSS
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum ChannelWidth {
+    Mhz20,
+    Mhz40,
+    Mhz80,
+    Mhz160,
+    Mhz80Plus80,
+}
+
+impl Frequency {
+    pub fn mhz(&self) -> u32 {
+        self.0
+    }
+
+    pub fn band(&self) -> Option<Band> {
+        self.channel().map(|channel| channel.band)
+    }
+
+    /// The channel of a 20 MHz channel center frequency, if it is one
+    pub fn channel(&self) -> Option<Channel> {
+        let mhz = self.0;
+        let (band, number) = match mhz {
+            2484 => (Band::TwoGhz, 14),
+            2412..=2472 => (Band::TwoGhz, (mhz - 2407) / 5),
+            5160..=5885 => (Band::FiveGhz, (mhz - 5000) / 5),
+            // channel 2 is the only one not on the 20 MHz raster of the band
+            5935 => (Band::SixGhz, 2),
+            5955..=7115 => (Band::SixGhz, (mhz - 5950) / 5),
+            58320..=69120 => {
+                if !(mhz - 58320).is_multiple_of(2160) {
+                    return None;
+                }
+                (Band::SixtyGhz, (mhz - 58320) / 2160 + 1)
+            }
+            _ => return None,
+        };
+        // other numbers are the centers of wider channels, or not used at all
+        let is_20mhz_channel = match band {
+            Band::FiveGhz if number <= 144 => number.is_multiple_of(4),
+            Band::FiveGhz => number >= 149 && number % 4 == 1,
+            Band::SixGhz => number == 2 || number % 4 == 1,
+            Band::TwoGhz | Band::SixtyGhz => true,
+        };
+        if !is_20mhz_channel {
+            return None;
+        }
+        let channel = Channel {
+            band,
+            number: number as u8,
+        };
+        // reject frequencies between channels
+        (channel.frequency() == *self).then_some(channel)
+    }
+
+    /// Whether this is a 6 GHz preferred scanning channel, which 6 GHz APs are discovered on
+    pub fn is_psc(&self) -> bool {
+        self.channel().is_some_and(|channel| channel.is_psc())
+    }
+}
+
+impl Channel {
+    /// A 20 MHz channel, if the band has one with that number
+    pub fn new(band: Band, number: u8) -> Option<Channel> {
+        let channel = Channel { band, number };
+        channel
+            .frequency()
+            .channel()
+            .filter(|valid| *valid == channel)
+    }
+
+    pub fn frequency(&self) -> Frequency {
+        let number = self.number as u32;
+        let mhz = match (self.band, number) {
+            (Band::TwoGhz, 14) => 2484,
+            (Band::TwoGhz, _) => 2407 + number * 5,
+            (Band::FiveGhz, _) => 5000 + number * 5,
+            (Band::SixGhz, 2) => 5935,
+            (Band::SixGhz, _) => 5950 + number * 5,
+            (Band::SixtyGhz, _) => 58320 + number.saturating_sub(1) * 2160,
+        };
+        Frequency(mhz)
+    }
+
+    /// Whether this is a 6 GHz preferred scanning channel: every fourth 20 MHz channel, starting
+    /// with channel 5
+    pub fn is_psc(&self) -> bool {
+        self.band == Band::SixGhz && self.number >= 5 && (self.number - 5).is_multiple_of(16)
+    }
+}
+
+impl ChannelWidth {
+    pub fn mhz(&self) -> u32 {
+        match self {
+            ChannelWidth::Mhz20 => 20,
+            ChannelWidth::Mhz40 => 40,
+            ChannelWidth::Mhz80 => 80,
+            ChannelWidth::Mhz160 | ChannelWidth::Mhz80Plus80 => 160,
+        }
+    }
+
+    /// Parses the `vht_oper_chwidth` or `he_oper_chwidth` value of hostapd, in which 0 stands for
+    /// 20 or 40 MHz, depending on the secondary channel
+    pub(crate) fn from_oper_chwidth(chwidth: u8, secondary_channel: bool) -> Option<ChannelWidth> {
+        match chwidth {
+            0 if secondary_channel => Some(ChannelWidth::Mhz40),
+            0 => Some(ChannelWidth::Mhz20),
+            1 => Some(ChannelWidth::Mhz80),
+            2 => Some(ChannelWidth::Mhz160),
+            3 => Some(ChannelWidth::Mhz80Plus80),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Frequency {
+    type Err = error::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        s.trim()
+            .parse()
+            .map(Frequency)
+            .map_err(|_| error::Error::InvalidFrequency(s.into()))
+    }
+}
+
+impl fmt::Display for Frequency {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} MHz", self.0)
+    }
+}
+
+impl fmt::Display for Band {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Band::TwoGhz => "2.4 GHz",
+            Band::FiveGhz => "5 GHz",
+            Band::SixGhz => "6 GHz",
+            Band::SixtyGhz => "60 GHz",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl fmt::Display for ChannelWidth {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ChannelWidth::Mhz80Plus80 => write!(f, "80+80 MHz"),
+            width => write!(f, "{} MHz", width.mhz()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn channel(band: Band, number: u8) -> Option<Channel> {
+        Some(Channel { band, number })
+    }
+
+    #[test]
+    fn maps_frequencies_to_channels() {
+        let cases = [
+            (2412, channel(Band::TwoGhz, 1)),
+            (2472, channel(Band::TwoGhz, 13)),
+            (2484, channel(Band::TwoGhz, 14)),
+            (5180, channel(Band::FiveGhz, 36)),
+            (5720, channel(Band::FiveGhz, 144)),
+            (5745, channel(Band::FiveGhz, 149)),
+            (5825, channel(Band::FiveGhz, 165)),
+            (5885, channel(Band::FiveGhz, 177)),
+            (5935, channel(Band::SixGhz, 2)),
+            (5955, channel(Band::SixGhz, 1)),
+            (5975, channel(Band::SixGhz, 5)),
+            (7115, channel(Band::SixGhz, 233)),
+            (58320, channel(Band::SixtyGhz, 1)),
+            (60480, channel(Band::SixtyGhz, 2)),
+            (69120, channel(Band::SixtyGhz, 6)),
+            // centers of wider channels, or unused numbers
+            (5165, None),
+            (5190, None),
+            (5725, None),
+            (5965, None),
+            (5985, None),
+            // between channels
+            (2414, None),
+            (2478, None),
+            (5182, None),
+            (5940, None),
+            (5957, None),
+            (58321, None),
+            (59000, None),
+            // outside of the bands
+            (0, None),
+            (2400, None),
+            (7120, None),
+            (71280, None),
+        ];
+        for (mhz, expected) in cases {
+            assert_eq!(Frequency(mhz).channel(), expected, "mapping {mhz} MHz");
+            if let Some(channel) = expected {
+                assert_eq!(channel.frequency(), Frequency(mhz), "mapping {channel:?}");
+                assert_eq!(Frequency(mhz).band(), Some(channel.band));
+            }
+        }
+    }
+
+    #[test]
+    fn validates_channels() {
+        let cases = [
+            (Band::TwoGhz, 1, Some(2412)),
+            (Band::TwoGhz, 14, Some(2484)),
+            (Band::TwoGhz, 0, None),
+            (Band::TwoGhz, 15, None),
+            (Band::FiveGhz, 36, Some(5180)),
+            (Band::FiveGhz, 177, Some(5885)),
+            (Band::FiveGhz, 33, None),
+            (Band::FiveGhz, 38, None),
+            (Band::FiveGhz, 149, Some(5745)),
+            (Band::FiveGhz, 151, None),
+            (Band::FiveGhz, 200, None),
+            (Band::SixGhz, 1, Some(5955)),
+            (Band::SixGhz, 2, Some(5935)),
+            (Band::SixGhz, 3, None),
+            (Band::SixGhz, 7, None),
+            (Band::SixGhz, 233, Some(7115)),
+            (Band::SixGhz, 234, None),
+            (Band::SixtyGhz, 1, Some(58320)),
+            (Band::SixtyGhz, 6, Some(69120)),
+            (Band::SixtyGhz, 0, None),
+            (Band::SixtyGhz, 7, None),
+        ];
+        for (band, number, expected) in cases {
+            assert_eq!(
+                Channel::new(band, number).map(|channel| channel.frequency()),
+                expected.map(Frequency),
+                "validating {band} channel {number}"
+            );
+        }
+    }
+
+    #[test]
+    fn finds_preferred_scanning_channels() {
+        assert!(Frequency(5975).is_psc());
+        assert!(Frequency(6295).is_psc());
+        assert!(!Frequency(5955).is_psc());
+        assert!(!Frequency(5935).is_psc());
+        assert!(!Frequency(5180).is_psc());
+    }
+}
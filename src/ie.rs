@@ -0,0 +1,1252 @@
+use serde::Serialize;
+use std::fmt;
+
+/// An information element, as found in beacons and probe responses (eg: the `ie` and `beacon_ie`
+/// fields of `sta::BssInfo`). Elements which are not decoded, or whose contents are malformed,
+/// are preserved as `Unknown` with their raw bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InformationElement {
+    /// SSID of the network. SSIDs are arbitrary bytes, see `ssid_string` for a lossy conversion.
+    Ssid(Vec<u8>),
+    SupportedRates(Vec<Rate>),
+    ExtendedSupportedRates(Vec<Rate>),
+    /// DS parameter set, with the current channel
+    DsParameter {
+        channel: u8,
+    },
+    Country(Country),
+    BssLoad(BssLoad),
+    HtCapabilities(HtCapabilities),
+    HtOperation(HtOperation),
+    Rsn(Rsn),
+    MobilityDomain(MobilityDomain),
+    ExtendedCapabilities(ExtendedCapabilities),
+    VhtCapabilities(VhtCapabilities),
+    VhtOperation(VhtOperation),
+    HeCapabilities(HeCapabilities),
+    HeOperation(HeOperation),
+    /// Vendor specific element of the original WPA
+    Wpa(Wpa),
+    /// Vendor specific element of Wi-Fi Multimedia
+    Wmm(Wmm),
+    /// Vendor specific element of Wi-Fi Protected Setup
+    Wps(Wps),
+    /// Any other vendor specific element
+    Vendor {
+        oui: [u8; 3],
+        data: Vec<u8>,
+    },
+    /// Any other element. Elements using the extension ID (255) have their extension ID as the
+    /// first byte of `data`.
+    Unknown {
+        id: u8,
+        data: Vec<u8>,
+    },
+}
+
+/// Element IDs of the decoded elements
+mod id {
+    pub const SSID: u8 = 0;
+    pub const SUPPORTED_RATES: u8 = 1;
+    pub const DS_PARAMETER: u8 = 3;
+    pub const COUNTRY: u8 = 7;
+    pub const BSS_LOAD: u8 = 11;
+    pub const HT_CAPABILITIES: u8 = 45;
+    pub const RSN: u8 = 48;
+    pub const EXTENDED_SUPPORTED_RATES: u8 = 50;
+    pub const MOBILITY_DOMAIN: u8 = 54;
+    pub const HT_OPERATION: u8 = 61;
+    pub const EXTENDED_CAPABILITIES: u8 = 127;
+    pub const VHT_CAPABILITIES: u8 = 191;
+    pub const VHT_OPERATION: u8 = 192;
+    pub const VENDOR_SPECIFIC: u8 = 221;
+    pub const EXTENSION: u8 = 255;
+    pub const EXT_HE_CAPABILITIES: u8 = 35;
+    pub const EXT_HE_OPERATION: u8 = 36;
+}
+
+/// OUI of the IEEE 802.11 cipher and AKM suites
+const OUI_IEEE: [u8; 3] = [0x00, 0x0f, 0xac];
+/// OUI of Microsoft, used by the WPA, WMM and WPS vendor specific elements
+const OUI_MICROSOFT: [u8; 3] = [0x00, 0x50, 0xf2];
+/// OUI of the Wi-Fi Alliance
+const OUI_WFA: [u8; 3] = [0x50, 0x6f, 0x9a];
+
+/// Parses a sequence of information elements. Parsing stops at an element whose length exceeds
+/// the remaining bytes.
+pub fn parse(mut bytes: &[u8]) -> Vec<InformationElement> {
+    let mut elements = Vec::new();
+    while let [id, len, rest @ ..] = bytes {
+        let len = *len as usize;
+        if rest.len() < len {
+            break;
+        }
+        let (data, next) = rest.split_at(len);
+        elements.push(InformationElement::parse(*id, data));
+        bytes = next;
+    }
+    elements
+}
+
+impl InformationElement {
+    /// Decodes the body of an element with the given ID
+    pub fn parse(id: u8, data: &[u8]) -> InformationElement {
+        Self::parse_known(id, data).unwrap_or_else(|| InformationElement::Unknown {
+            id,
+            data: data.to_vec(),
+        })
+    }
+
+    fn parse_known(id: u8, data: &[u8]) -> Option<InformationElement> {
+        let element = match id {
+            id::SSID => InformationElement::Ssid(data.to_vec()),
+            id::SUPPORTED_RATES => InformationElement::SupportedRates(Rate::vec_from_bytes(data)),
+            id::EXTENDED_SUPPORTED_RATES => {
+                InformationElement::ExtendedSupportedRates(Rate::vec_from_bytes(data))
+            }
+            id::DS_PARAMETER => InformationElement::DsParameter {
+                channel: *data.first()?,
+            },
+            id::COUNTRY => InformationElement::Country(Country::from_bytes(data)?),
+            id::BSS_LOAD => InformationElement::BssLoad(BssLoad::from_bytes(data)?),
+            id::HT_CAPABILITIES => {
+                InformationElement::HtCapabilities(HtCapabilities::from_bytes(data)?)
+            }
+            id::HT_OPERATION => InformationElement::HtOperation(HtOperation::from_bytes(data)?),
+            id::RSN => InformationElement::Rsn(Rsn::from_bytes(data)?),
+            id::MOBILITY_DOMAIN => {
+                InformationElement::MobilityDomain(MobilityDomain::from_bytes(data)?)
+            }
+            id::EXTENDED_CAPABILITIES => {
+                InformationElement::ExtendedCapabilities(ExtendedCapabilities(data.to_vec()))
+            }
+            id::VHT_CAPABILITIES => {
+                InformationElement::VhtCapabilities(VhtCapabilities::from_bytes(data)?)
+            }
+            id::VHT_OPERATION => InformationElement::VhtOperation(VhtOperation::from_bytes(data)?),
+            id::EXTENSION => match data.split_first()? {
+                (&id::EXT_HE_CAPABILITIES, data) => {
+                    InformationElement::HeCapabilities(HeCapabilities::from_bytes(data)?)
+                }
+                (&id::EXT_HE_OPERATION, data) => {
+                    InformationElement::HeOperation(HeOperation::from_bytes(data)?)
+                }
+                _ => return None,
+            },
+            id::VENDOR_SPECIFIC => Self::parse_vendor(data)?,
+            _ => return None,
+        };
+        Some(element)
+    }
+
+    fn parse_vendor(data: &[u8]) -> Option<InformationElement> {
+        let mut reader = Reader::new(data);
+        let oui = reader.array()?;
+        let element = match (oui, reader.peek()) {
+            (OUI_MICROSOFT, Some(1)) => {
+                reader.u8()?;
+                InformationElement::Wpa(Wpa::from_reader(&mut reader)?)
+            }
+            (OUI_MICROSOFT, Some(2)) => {
+                reader.u8()?;
+                InformationElement::Wmm(Wmm::from_reader(&mut reader)?)
+            }
+            (OUI_MICROSOFT, Some(4)) => {
+                reader.u8()?;
+                InformationElement::Wps(Wps::from_bytes(reader.rest())?)
+            }
+            _ => InformationElement::Vendor {
+                oui,
+                data: reader.rest().to_vec(),
+            },
+        };
+        Some(element)
+    }
+
+    /// The SSID as a string, replacing invalid UTF-8 sequences, if this is an SSID element
+    pub fn ssid_string(&self) -> Option<String> {
+        match self {
+            InformationElement::Ssid(ssid) => Some(String::from_utf8_lossy(ssid).into_owned()),
+            _ => None,
+        }
+    }
+}
+
+/// Reads little endian fields from the body of an element
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.data.first().copied()
+    }
+
+    fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.data.len() < len {
+            return None;
+        }
+        let (bytes, rest) = self.data.split_at(len);
+        self.data = rest;
+        Some(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Option<[u8; N]> {
+        self.bytes(N)?.try_into().ok()
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        Some(self.array::<1>()?[0])
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        Some(u16::from_le_bytes(self.array()?))
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.array()?))
+    }
+
+    fn rest(&mut self) -> &'a [u8] {
+        std::mem::take(&mut self.data)
+    }
+
+    /// Reads a 16 bit count followed by that many suites
+    fn suites<const N: usize, T>(&mut self, from_suite: impl Fn([u8; N]) -> T) -> Option<Vec<T>> {
+        let count = self.u16()?;
+        (0..count).map(|_| self.array().map(&from_suite)).collect()
+    }
+}
+
+/// A rate of the supported rates elements
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rate {
+    /// Rate in units of 500 kbps. Values which are not rates, such as 127 for the HT PHY, are BSS
+    /// membership selectors.
+    pub rate: u8,
+    /// The rate is part of the basic rate set, which every station must support
+    pub basic: bool,
+}
+
+impl Rate {
+    fn vec_from_bytes(data: &[u8]) -> Vec<Rate> {
+        data.iter()
+            .map(|byte| Rate {
+                rate: byte & 0x7f,
+                basic: byte & 0x80 != 0,
+            })
+            .collect()
+    }
+
+    pub fn kbps(&self) -> u32 {
+        self.rate as u32 * 500
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Country {
+    /// ISO 3166-1 country code, eg: `US`
+    pub code: String,
+    /// Environment of the regulations: `b' '` for any, `b'I'` for indoor, `b'O'` for outdoor
+    pub environment: u8,
+    pub triplets: Vec<CountryTriplet>,
+}
+
+/// Subband of the country element. When `first_channel` is 201 or more, the triplet is an
+/// operating class triplet instead, with the operating extension identifier, operating class
+/// and coverage class as its fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CountryTriplet {
+    pub first_channel: u8,
+    pub num_channels: u8,
+    /// Maximum transmit power in dBm
+    pub max_tx_power: i8,
+}
+
+impl Country {
+    fn from_bytes(data: &[u8]) -> Option<Country> {
+        let mut reader = Reader::new(data);
+        let code = reader.bytes(2)?;
+        let environment = reader.u8()?;
+        let mut triplets = Vec::new();
+        // the element is padded to an even length
+        while let Some([first_channel, num_channels, max_tx_power]) = reader.array() {
+            triplets.push(CountryTriplet {
+                first_channel,
+                num_channels,
+                max_tx_power: max_tx_power as i8,
+            });
+        }
+        Some(Country {
+            code: String::from_utf8_lossy(code).into_owned(),
+            environment,
+            triplets,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BssLoad {
+    pub station_count: u16,
+    /// Percentage of time the AP sensed the medium busy, scaled to 255
+    pub channel_utilization: u8,
+    /// Remaining medium time available, in units of 32 µs per second
+    pub available_admission_capacity: u16,
+}
+
+impl BssLoad {
+    fn from_bytes(data: &[u8]) -> Option<BssLoad> {
+        let mut reader = Reader::new(data);
+        Some(BssLoad {
+            station_count: reader.u16()?,
+            channel_utilization: reader.u8()?,
+            available_admission_capacity: reader.u16()?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HtCapabilities {
+    pub info: u16,
+    pub ampdu_parameters: u8,
+    /// Supported MCS set, starting with the receive MCS bitmask
+    pub supported_mcs_set: [u8; 16],
+    pub extended_capabilities: u16,
+    pub transmit_beamforming: u32,
+    pub asel_capabilities: u8,
+}
+
+impl HtCapabilities {
+    fn from_bytes(data: &[u8]) -> Option<HtCapabilities> {
+        let mut reader = Reader::new(data);
+        Some(HtCapabilities {
+            info: reader.u16()?,
+            ampdu_parameters: reader.u8()?,
+            supported_mcs_set: reader.array()?,
+            extended_capabilities: reader.u16()?,
+            transmit_beamforming: reader.u32()?,
+            asel_capabilities: reader.u8()?,
+        })
+    }
+
+    pub fn supports_40mhz(&self) -> bool {
+        self.info & (1 << 1) != 0
+    }
+
+    pub fn short_gi_20mhz(&self) -> bool {
+        self.info & (1 << 5) != 0
+    }
+
+    pub fn short_gi_40mhz(&self) -> bool {
+        self.info & (1 << 6) != 0
+    }
+
+    /// Number of spatial streams the station can receive, from the receive MCS bitmask
+    pub fn max_spatial_streams(&self) -> u8 {
+        self.supported_mcs_set[..4]
+            .iter()
+            .filter(|mcs| **mcs != 0)
+            .count() as u8
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HtOperation {
+    pub primary_channel: u8,
+    pub info: [u8; 5],
+    pub basic_mcs_set: [u8; 16],
+}
+
+impl HtOperation {
+    fn from_bytes(data: &[u8]) -> Option<HtOperation> {
+        let mut reader = Reader::new(data);
+        Some(HtOperation {
+            primary_channel: reader.u8()?,
+            info: reader.array()?,
+            basic_mcs_set: reader.array()?,
+        })
+    }
+
+    /// Position of the secondary channel: 1 if above the primary channel, 3 if below, 0 if there
+    /// is none
+    pub fn secondary_channel_offset(&self) -> u8 {
+        self.info[0] & 0x03
+    }
+
+    /// Whether channels wider than 20 MHz may be used
+    pub fn any_channel_width(&self) -> bool {
+        self.info[0] & (1 << 2) != 0
+    }
+}
+
+/// Cipher suite of the RSN and WPA elements
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CipherSuite {
+    /// Use the group cipher suite as the pairwise cipher suite
+    UseGroup,
+    Wep40,
+    Tkip,
+    Ccmp,
+    Wep104,
+    BipCmac128,
+    GroupAddressedTrafficNotAllowed,
+    Gcmp,
+    Gcmp256,
+    Ccmp256,
+    BipGmac128,
+    BipGmac256,
+    BipCmac256,
+    Other {
+        oui: [u8; 3],
+        suite_type: u8,
+    },
+}
+
+impl CipherSuite {
+    const NAMED: [CipherSuite; 13] = [
+        CipherSuite::UseGroup,
+        CipherSuite::Wep40,
+        CipherSuite::Tkip,
+        CipherSuite::Ccmp,
+        CipherSuite::Wep104,
+        CipherSuite::BipCmac128,
+        CipherSuite::GroupAddressedTrafficNotAllowed,
+        CipherSuite::Gcmp,
+        CipherSuite::Gcmp256,
+        CipherSuite::Ccmp256,
+        CipherSuite::BipGmac128,
+        CipherSuite::BipGmac256,
+        CipherSuite::BipCmac256,
+    ];
+
+    /// Parses the name used by wpa_supplicant, which is also the one displayed, eg: `CCMP-256`.
+    /// wpa_supplicant names the use of the group cipher suite `NONE`.
+    pub(crate) fn from_name(name: &str) -> Option<CipherSuite> {
+        if name == "NONE" {
+            return Some(CipherSuite::UseGroup);
+        }
+        Self::NAMED
+            .into_iter()
+            .find(|suite| suite.to_string() == name)
+    }
+
+    fn from_suite([a, b, c, suite_type]: [u8; 4]) -> CipherSuite {
+        let oui = [a, b, c];
+        // the WPA element uses the Microsoft OUI with the same suite types
+        if oui != OUI_IEEE && !(oui == OUI_MICROSOFT && suite_type <= 5) {
+            return CipherSuite::Other { oui, suite_type };
+        }
+        match suite_type {
+            0 => CipherSuite::UseGroup,
+            1 => CipherSuite::Wep40,
+            2 => CipherSuite::Tkip,
+            4 => CipherSuite::Ccmp,
+            5 => CipherSuite::Wep104,
+            6 => CipherSuite::BipCmac128,
+            7 => CipherSuite::GroupAddressedTrafficNotAllowed,
+            8 => CipherSuite::Gcmp,
+            9 => CipherSuite::Gcmp256,
+            10 => CipherSuite::Ccmp256,
+            11 => CipherSuite::BipGmac128,
+            12 => CipherSuite::BipGmac256,
+            13 => CipherSuite::BipCmac256,
+            suite_type => CipherSuite::Other { oui, suite_type },
+        }
+    }
+}
+
+impl fmt::Display for CipherSuite {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CipherSuite::UseGroup => write!(f, "USE-GROUP"),
+            CipherSuite::Wep40 => write!(f, "WEP-40"),
+            CipherSuite::Tkip => write!(f, "TKIP"),
+            CipherSuite::Ccmp => write!(f, "CCMP"),
+            CipherSuite::Wep104 => write!(f, "WEP-104"),
+            CipherSuite::BipCmac128 => write!(f, "BIP-CMAC-128"),
+            CipherSuite::GroupAddressedTrafficNotAllowed => write!(f, "GTK-NOT-USED"),
+            CipherSuite::Gcmp => write!(f, "GCMP"),
+            CipherSuite::Gcmp256 => write!(f, "GCMP-256"),
+            CipherSuite::Ccmp256 => write!(f, "CCMP-256"),
+            CipherSuite::BipGmac128 => write!(f, "BIP-GMAC-128"),
+            CipherSuite::BipGmac256 => write!(f, "BIP-GMAC-256"),
+            CipherSuite::BipCmac256 => write!(f, "BIP-CMAC-256"),
+            CipherSuite::Other {
+                oui: [a, b, c],
+                suite_type,
+            } => write!(f, "{a:02x}-{b:02x}-{c:02x}:{suite_type}"),
+        }
+    }
+}
+
+/// Authentication and key management suite of the RSN and WPA elements
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AkmSuite {
+    Ieee8021x,
+    Psk,
+    FtIeee8021x,
+    FtPsk,
+    Ieee8021xSha256,
+    PskSha256,
+    Tdls,
+    Sae,
+    FtSae,
+    SuiteB,
+    SuiteB192,
+    FtIeee8021xSha384,
+    FilsSha256,
+    FilsSha384,
+    FtFilsSha256,
+    FtFilsSha384,
+    Owe,
+    SaeExtKey,
+    FtSaeExtKey,
+    Dpp,
+    Other { oui: [u8; 3], suite_type: u8 },
+}
+
+impl AkmSuite {
+    const NAMED: [AkmSuite; 20] = [
+        AkmSuite::Ieee8021x,
+        AkmSuite::Psk,
+        AkmSuite::FtIeee8021x,
+        AkmSuite::FtPsk,
+        AkmSuite::Ieee8021xSha256,
+        AkmSuite::PskSha256,
+        AkmSuite::Tdls,
+        AkmSuite::Sae,
+        AkmSuite::FtSae,
+        AkmSuite::SuiteB,
+        AkmSuite::SuiteB192,
+        AkmSuite::FtIeee8021xSha384,
+        AkmSuite::FilsSha256,
+        AkmSuite::FilsSha384,
+        AkmSuite::FtFilsSha256,
+        AkmSuite::FtFilsSha384,
+        AkmSuite::Owe,
+        AkmSuite::SaeExtKey,
+        AkmSuite::FtSaeExtKey,
+        AkmSuite::Dpp,
+    ];
+
+    /// Parses the name used by wpa_supplicant, which is also the one displayed, eg: `FT/PSK`
+    pub(crate) fn from_name(name: &str) -> Option<AkmSuite> {
+        Self::NAMED
+            .into_iter()
+            .find(|suite| suite.to_string() == name)
+    }
+
+    fn from_suite([a, b, c, suite_type]: [u8; 4]) -> AkmSuite {
+        let oui = [a, b, c];
+        match (oui, suite_type) {
+            (OUI_IEEE | OUI_MICROSOFT, 1) => AkmSuite::Ieee8021x,
+            (OUI_IEEE | OUI_MICROSOFT, 2) => AkmSuite::Psk,
+            (OUI_IEEE, 3) => AkmSuite::FtIeee8021x,
+            (OUI_IEEE, 4) => AkmSuite::FtPsk,
+            (OUI_IEEE, 5) => AkmSuite::Ieee8021xSha256,
+            (OUI_IEEE, 6) => AkmSuite::PskSha256,
+            (OUI_IEEE, 7) => AkmSuite::Tdls,
+            (OUI_IEEE, 8) => AkmSuite::Sae,
+            (OUI_IEEE, 9) => AkmSuite::FtSae,
+            (OUI_IEEE, 11) => AkmSuite::SuiteB,
+            (OUI_IEEE, 12) => AkmSuite::SuiteB192,
+            (OUI_IEEE, 13) => AkmSuite::FtIeee8021xSha384,
+            (OUI_IEEE, 14) => AkmSuite::FilsSha256,
+            (OUI_IEEE, 15) => AkmSuite::FilsSha384,
+            (OUI_IEEE, 16) => AkmSuite::FtFilsSha256,
+            (OUI_IEEE, 17) => AkmSuite::FtFilsSha384,
+            (OUI_IEEE, 18) => AkmSuite::Owe,
+            (OUI_IEEE, 24) => AkmSuite::SaeExtKey,
+            (OUI_IEEE, 25) => AkmSuite::FtSaeExtKey,
+            (OUI_WFA, 2) => AkmSuite::Dpp,
+            (oui, suite_type) => AkmSuite::Other { oui, suite_type },
+        }
+    }
+}
+
+impl fmt::Display for AkmSuite {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AkmSuite::Ieee8021x => write!(f, "EAP"),
+            AkmSuite::Psk => write!(f, "PSK"),
+            AkmSuite::FtIeee8021x => write!(f, "FT/EAP"),
+            AkmSuite::FtPsk => write!(f, "FT/PSK"),
+            AkmSuite::Ieee8021xSha256 => write!(f, "EAP-SHA256"),
+            AkmSuite::PskSha256 => write!(f, "PSK-SHA256"),
+            AkmSuite::Tdls => write!(f, "TDLS"),
+            AkmSuite::Sae => write!(f, "SAE"),
+            AkmSuite::FtSae => write!(f, "FT/SAE"),
+            AkmSuite::SuiteB => write!(f, "EAP-SUITE-B"),
+            AkmSuite::SuiteB192 => write!(f, "EAP-SUITE-B-192"),
+            AkmSuite::FtIeee8021xSha384 => write!(f, "FT/EAP-SHA384"),
+            AkmSuite::FilsSha256 => write!(f, "FILS-SHA256"),
+            AkmSuite::FilsSha384 => write!(f, "FILS-SHA384"),
+            AkmSuite::FtFilsSha256 => write!(f, "FT-FILS-SHA256"),
+            AkmSuite::FtFilsSha384 => write!(f, "FT-FILS-SHA384"),
+            AkmSuite::Owe => write!(f, "OWE"),
+            AkmSuite::SaeExtKey => write!(f, "SAE-EXT-KEY"),
+            AkmSuite::FtSaeExtKey => write!(f, "FT/SAE-EXT-KEY"),
+            AkmSuite::Dpp => write!(f, "DPP"),
+            AkmSuite::Other {
+                oui: [a, b, c],
+                suite_type,
+            } => write!(f, "{a:02x}-{b:02x}-{c:02x}:{suite_type}"),
+        }
+    }
+}
+
+impl Serialize for CipherSuite {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl Serialize for AkmSuite {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Robust Security Network element, describing the WPA2/WPA3 security of the network. Fields
+/// after the pairwise cipher suites are optional.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rsn {
+    pub version: u16,
+    pub group_cipher: CipherSuite,
+    pub pairwise_ciphers: Vec<CipherSuite>,
+    pub akm_suites: Vec<AkmSuite>,
+    pub capabilities: Option<RsnCapabilities>,
+    pub pmkids: Vec<[u8; 16]>,
+    pub group_management_cipher: Option<CipherSuite>,
+}
+
+impl Rsn {
+    fn from_bytes(data: &[u8]) -> Option<Rsn> {
+        let mut reader = Reader::new(data);
+        let version = reader.u16()?;
+        let group_cipher = CipherSuite::from_suite(reader.array()?);
+        let mut rsn = Rsn {
+            version,
+            group_cipher,
+            pairwise_ciphers: Vec::new(),
+            akm_suites: Vec::new(),
+            capabilities: None,
+            pmkids: Vec::new(),
+            group_management_cipher: None,
+        };
+        if reader.is_empty() {
+            return Some(rsn);
+        }
+        rsn.pairwise_ciphers = reader.suites(CipherSuite::from_suite)?;
+        if reader.is_empty() {
+            return Some(rsn);
+        }
+        rsn.akm_suites = reader.suites(AkmSuite::from_suite)?;
+        if reader.is_empty() {
+            return Some(rsn);
+        }
+        rsn.capabilities = Some(RsnCapabilities(reader.u16()?));
+        if reader.is_empty() {
+            return Some(rsn);
+        }
+        rsn.pmkids = reader.suites(|pmkid: [u8; 16]| pmkid)?;
+        if reader.is_empty() {
+            return Some(rsn);
+        }
+        rsn.group_management_cipher = Some(CipherSuite::from_suite(reader.array()?));
+        Some(rsn)
+    }
+
+    /// Whether management frame protection is required to join the network
+    pub fn mfp_required(&self) -> bool {
+        self.capabilities.is_some_and(|caps| caps.mfp_required())
+    }
+}
+
+/// RSN capabilities field
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RsnCapabilities(pub u16);
+
+impl RsnCapabilities {
+    pub fn preauthentication(&self) -> bool {
+        self.0 & (1 << 0) != 0
+    }
+
+    pub fn no_pairwise(&self) -> bool {
+        self.0 & (1 << 1) != 0
+    }
+
+    pub fn mfp_required(&self) -> bool {
+        self.0 & (1 << 6) != 0
+    }
+
+    pub fn mfp_capable(&self) -> bool {
+        self.0 & (1 << 7) != 0
+    }
+
+    pub fn extended_key_id(&self) -> bool {
+        self.0 & (1 << 13) != 0
+    }
+
+    /// Operating channel validation capable
+    pub fn ocv_capable(&self) -> bool {
+        self.0 & (1 << 14) != 0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MobilityDomain {
+    /// Mobility domain identifier, shared by the APs between which fast transition is possible
+    pub mdid: u16,
+    pub ft_capability: u8,
+}
+
+impl MobilityDomain {
+    fn from_bytes(data: &[u8]) -> Option<MobilityDomain> {
+        let mut reader = Reader::new(data);
+        Some(MobilityDomain {
+            mdid: reader.u16()?,
+            ft_capability: reader.u8()?,
+        })
+    }
+
+    /// Whether fast transition over the distribution system is supported
+    pub fn ft_over_ds(&self) -> bool {
+        self.ft_capability & (1 << 0) != 0
+    }
+}
+
+/// Extended capabilities element, a bitfield of variable length
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtendedCapabilities(pub Vec<u8>);
+
+impl ExtendedCapabilities {
+    /// Whether the capability at the given bit is set. Bits past the end of the element are not.
+    pub fn has(&self, bit: usize) -> bool {
+        self.0
+            .get(bit / 8)
+            .is_some_and(|byte| byte & (1 << (bit % 8)) != 0)
+    }
+
+    /// BSS transition management (802.11v)
+    pub fn bss_transition(&self) -> bool {
+        self.has(19)
+    }
+
+    /// Interworking (802.11u), used by Hotspot 2.0
+    pub fn interworking(&self) -> bool {
+        self.has(31)
+    }
+
+    pub fn utf8_ssid(&self) -> bool {
+        self.has(48)
+    }
+
+    pub fn operating_mode_notification(&self) -> bool {
+        self.has(62)
+    }
+
+    pub fn twt_requester(&self) -> bool {
+        self.has(77)
+    }
+
+    pub fn twt_responder(&self) -> bool {
+        self.has(78)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VhtCapabilities {
+    pub info: u32,
+    pub rx_mcs_map: u16,
+    pub rx_highest_rate: u16,
+    pub tx_mcs_map: u16,
+    pub tx_highest_rate: u16,
+}
+
+impl VhtCapabilities {
+    fn from_bytes(data: &[u8]) -> Option<VhtCapabilities> {
+        let mut reader = Reader::new(data);
+        Some(VhtCapabilities {
+            info: reader.u32()?,
+            rx_mcs_map: reader.u16()?,
+            rx_highest_rate: reader.u16()?,
+            tx_mcs_map: reader.u16()?,
+            tx_highest_rate: reader.u16()?,
+        })
+    }
+
+    /// 0 if neither 160 nor 80+80 MHz are supported, 1 for 160 MHz, 2 for 160 and 80+80 MHz
+    pub fn supported_channel_width_set(&self) -> u8 {
+        ((self.info >> 2) & 0x03) as u8
+    }
+
+    pub fn short_gi_80mhz(&self) -> bool {
+        self.info & (1 << 5) != 0
+    }
+
+    pub fn su_beamformer(&self) -> bool {
+        self.info & (1 << 11) != 0
+    }
+
+    pub fn mu_beamformer(&self) -> bool {
+        self.info & (1 << 19) != 0
+    }
+
+    /// Number of spatial streams the station can receive
+    pub fn max_spatial_streams(&self) -> u8 {
+        max_spatial_streams(self.rx_mcs_map)
+    }
+}
+
+/// Counts the streams of an MCS map, in which each stream has 2 bits, 3 meaning unsupported
+fn max_spatial_streams(mcs_map: u16) -> u8 {
+    (0..8)
+        .filter(|stream| (mcs_map >> (stream * 2)) & 0x03 != 0x03)
+        .count() as u8
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VhtOperation {
+    /// 0 for 20 or 40 MHz, 1 for 80, 160 or 80+80 MHz depending on the center frequency segments.
+    /// Older APs use the deprecated 2 for 160 MHz and 3 for 80+80 MHz.
+    pub channel_width: u8,
+    /// Channel number of the center of the channel, or of the primary 80 MHz for 160 MHz
+    pub center_freq_seg0: u8,
+    pub center_freq_seg1: u8,
+    pub basic_mcs_set: u16,
+}
+
+impl VhtOperation {
+    fn from_bytes(data: &[u8]) -> Option<VhtOperation> {
+        let mut reader = Reader::new(data);
+        Some(VhtOperation {
+            channel_width: reader.u8()?,
+            center_freq_seg0: reader.u8()?,
+            center_freq_seg1: reader.u8()?,
+            basic_mcs_set: reader.u16()?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeCapabilities {
+    pub mac_capabilities: [u8; 6],
+    pub phy_capabilities: [u8; 11],
+    /// Supported HE-MCS and NSS sets, each with a receive and a transmit map: one for up to
+    /// 80 MHz, followed by ones for 160 and 80+80 MHz when supported
+    pub mcs_nss: Vec<u16>,
+    pub ppe_thresholds: Vec<u8>,
+}
+
+impl HeCapabilities {
+    fn from_bytes(data: &[u8]) -> Option<HeCapabilities> {
+        let mut reader = Reader::new(data);
+        let mac_capabilities = reader.array()?;
+        let phy_capabilities: [u8; 11] = reader.array()?;
+        // the channel width set tells which of the 160 and 80+80 MHz maps are present
+        let mut maps = 2;
+        if phy_capabilities[0] & (1 << 3) != 0 {
+            maps += 2;
+        }
+        if phy_capabilities[0] & (1 << 4) != 0 {
+            maps += 2;
+        }
+        let mcs_nss = (0..maps).map(|_| reader.u16()).collect::<Option<_>>()?;
+        Some(HeCapabilities {
+            mac_capabilities,
+            phy_capabilities,
+            mcs_nss,
+            ppe_thresholds: reader.rest().to_vec(),
+        })
+    }
+
+    /// Number of spatial streams the station can receive at up to 80 MHz
+    pub fn max_spatial_streams(&self) -> u8 {
+        self.mcs_nss
+            .first()
+            .map_or(0, |map| max_spatial_streams(*map))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeOperation {
+    /// HE operation parameters (24 bits)
+    pub parameters: u32,
+    pub bss_color_info: u8,
+    pub basic_mcs_nss: u16,
+    /// Present when the VHT operation element is not
+    pub vht_operation: Option<VhtOperationInfo>,
+    pub max_cohosted_bssid: Option<u8>,
+    pub six_ghz_operation: Option<SixGhzOperation>,
+}
+
+/// VHT operation information carried in the HE operation element
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VhtOperationInfo {
+    pub channel_width: u8,
+    pub center_freq_seg0: u8,
+    pub center_freq_seg1: u8,
+}
+
+/// Channel of a 6 GHz BSS, which has no DS parameter set, HT or VHT operation elements
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SixGhzOperation {
+    pub primary_channel: u8,
+    /// Channel width in its two lowest bits: 0 for 20 MHz, 1 for 40, 2 for 80, 3 for 160 or
+    /// 80+80 MHz
+    pub control: u8,
+    pub center_freq_seg0: u8,
+    pub center_freq_seg1: u8,
+    /// Minimum rate in units of 1 Mbps
+    pub min_rate: u8,
+}
+
+impl HeOperation {
+    fn from_bytes(data: &[u8]) -> Option<HeOperation> {
+        let mut reader = Reader::new(data);
+        let [a, b, c] = reader.array()?;
+        let parameters = u32::from_le_bytes([a, b, c, 0]);
+        let bss_color_info = reader.u8()?;
+        let basic_mcs_nss = reader.u16()?;
+        let vht_operation = if parameters & (1 << 14) != 0 {
+            let [channel_width, center_freq_seg0, center_freq_seg1] = reader.array()?;
+            Some(VhtOperationInfo {
+                channel_width,
+                center_freq_seg0,
+                center_freq_seg1,
+            })
+        } else {
+            None
+        };
+        let max_cohosted_bssid = if parameters & (1 << 15) != 0 {
+            Some(reader.u8()?)
+        } else {
+            None
+        };
+        let six_ghz_operation = if parameters & (1 << 17) != 0 {
+            let [primary_channel, control, center_freq_seg0, center_freq_seg1, min_rate] =
+                reader.array()?;
+            Some(SixGhzOperation {
+                primary_channel,
+                control,
+                center_freq_seg0,
+                center_freq_seg1,
+                min_rate,
+            })
+        } else {
+            None
+        };
+        Some(HeOperation {
+            parameters,
+            bss_color_info,
+            basic_mcs_nss,
+            vht_operation,
+            max_cohosted_bssid,
+            six_ghz_operation,
+        })
+    }
+
+    pub fn bss_color(&self) -> u8 {
+        self.bss_color_info & 0x3f
+    }
+
+    pub fn bss_color_disabled(&self) -> bool {
+        self.bss_color_info & (1 << 7) != 0
+    }
+}
+
+/// Vendor specific element of the original WPA, which predates the RSN element
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Wpa {
+    pub version: u16,
+    pub group_cipher: CipherSuite,
+    pub pairwise_ciphers: Vec<CipherSuite>,
+    pub akm_suites: Vec<AkmSuite>,
+}
+
+impl Wpa {
+    fn from_reader(reader: &mut Reader) -> Option<Wpa> {
+        let version = reader.u16()?;
+        let group_cipher = CipherSuite::from_suite(reader.array()?);
+        let pairwise_ciphers = if reader.is_empty() {
+            Vec::new()
+        } else {
+            reader.suites(CipherSuite::from_suite)?
+        };
+        let akm_suites = if reader.is_empty() {
+            Vec::new()
+        } else {
+            reader.suites(AkmSuite::from_suite)?
+        };
+        Some(Wpa {
+            version,
+            group_cipher,
+            pairwise_ciphers,
+            akm_suites,
+        })
+    }
+}
+
+/// Vendor specific element of Wi-Fi Multimedia, either the information element or the parameter
+/// element with the parameters of each access category
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Wmm {
+    /// 0 for the information element, 1 for the parameter element
+    pub subtype: u8,
+    pub version: u8,
+    pub qos_info: u8,
+    /// Parameters of the best effort, background, video and voice access categories
+    pub ac_parameters: Vec<WmmAcParameters>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WmmAcParameters {
+    /// Access category index and arbitration inter-frame spacing number
+    pub aci_aifsn: u8,
+    /// Minimum and maximum contention window exponents
+    pub ecw: u8,
+    /// Transmit opportunity limit in units of 32 µs
+    pub txop_limit: u16,
+}
+
+impl Wmm {
+    fn from_reader(reader: &mut Reader) -> Option<Wmm> {
+        let subtype = reader.u8()?;
+        let version = reader.u8()?;
+        let qos_info = reader.u8()?;
+        let mut ac_parameters = Vec::new();
+        if subtype == 1 {
+            // reserved
+            reader.u8()?;
+            for _ in 0..4 {
+                ac_parameters.push(WmmAcParameters {
+                    aci_aifsn: reader.u8()?,
+                    ecw: reader.u8()?,
+                    txop_limit: reader.u16()?,
+                });
+            }
+        }
+        Some(Wmm {
+            subtype,
+            version,
+            qos_info,
+            ac_parameters,
+        })
+    }
+
+    /// Whether the AP supports unscheduled automatic power save delivery
+    pub fn uapsd(&self) -> bool {
+        self.qos_info & (1 << 7) != 0
+    }
+}
+
+/// Vendor specific element of Wi-Fi Protected Setup, made of big endian attributes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Wps {
+    pub attributes: Vec<WpsAttribute>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WpsAttribute {
+    pub id: u16,
+    pub data: Vec<u8>,
+}
+
+mod wps_attribute {
+    pub const CONFIG_METHODS: u16 = 0x1008;
+    pub const DEVICE_NAME: u16 = 0x1011;
+    pub const SELECTED_REGISTRAR: u16 = 0x1041;
+    pub const WPS_STATE: u16 = 0x1044;
+    pub const UUID_E: u16 = 0x1047;
+    pub const AP_SETUP_LOCKED: u16 = 0x1057;
+}
+
+impl Wps {
+    fn from_bytes(mut data: &[u8]) -> Option<Wps> {
+        let mut attributes = Vec::new();
+        while let [a, b, c, d, rest @ ..] = data {
+            let len = u16::from_be_bytes([*c, *d]) as usize;
+            if rest.len() < len {
+                return None;
+            }
+            let (value, next) = rest.split_at(len);
+            attributes.push(WpsAttribute {
+                id: u16::from_be_bytes([*a, *b]),
+                data: value.to_vec(),
+            });
+            data = next;
+        }
+        Some(Wps { attributes })
+    }
+
+    pub fn attribute(&self, id: u16) -> Option<&[u8]> {
+        self.attributes
+            .iter()
+            .find(|attribute| attribute.id == id)
+            .map(|attribute| attribute.data.as_slice())
+    }
+
+    /// Whether the AP is configured (as opposed to waiting for a registrar to configure it)
+    pub fn configured(&self) -> Option<bool> {
+        Some(*self.attribute(wps_attribute::WPS_STATE)?.first()? == 2)
+    }
+
+    /// Whether a registrar is ready to enroll stations, eg: after pushing the WPS button
+    pub fn selected_registrar(&self) -> bool {
+        self.attribute(wps_attribute::SELECTED_REGISTRAR)
+            .and_then(|value| value.first())
+            .is_some_and(|value| *value != 0)
+    }
+
+    pub fn ap_setup_locked(&self) -> bool {
+        self.attribute(wps_attribute::AP_SETUP_LOCKED)
+            .and_then(|value| value.first())
+            .is_some_and(|value| *value != 0)
+    }
+
+    pub fn config_methods(&self) -> Option<u16> {
+        let value = self.attribute(wps_attribute::CONFIG_METHODS)?;
+        Some(u16::from_be_bytes(value.try_into().ok()?))
+    }
+
+    pub fn device_name(&self) -> Option<String> {
+        let value = self.attribute(wps_attribute::DEVICE_NAME)?;
+        Some(String::from_utf8_lossy(value).into_owned())
+    }
+
+    pub fn uuid(&self) -> Option<[u8; 16]> {
+        self.attribute(wps_attribute::UUID_E)?.try_into().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_information_elements() {
+        let bytes = [
+            0x00, 0x04, b'h', b'o', b'm', b'e', // SSID
+            0x01, 0x03, 0x82, 0x84, 0x0c, // supported rates
+            0x03, 0x01, 0x06, // DS parameter
+            0x07, 0x06, b'U', b'S', b' ', 0x01, 0x0b, 0x1e, // country
+            0x30, 0x14, 0x01, 0x00, // RSN version
+            0x00, 0x0f, 0xac, 0x04, // group cipher
+            0x01, 0x00, 0x00, 0x0f, 0xac, 0x04, // pairwise ciphers
+            0x01, 0x00, 0x00, 0x0f, 0xac, 0x08, // AKM suites
+            0xc0, 0x00, // capabilities
+            0xdd, 0x07, 0x00, 0x50, 0xf2, 0x02, 0x00, 0x01, 0x80, // WMM
+            0xdd, 0x04, 0x00, 0x11, 0x22, 0x33, // vendor
+            0xff, 0x02, 0x6c, 0x01, // unknown extension
+            0x2a, 0x01, 0x00, // unknown
+            0x30, 0x02, 0x01, // truncated
+        ];
+        let elements = parse(&bytes);
+        assert_eq!(elements.len(), 9);
+        assert_eq!(elements[0].ssid_string(), Some("home".to_string()));
+        assert_eq!(
+            elements[1],
+            InformationElement::SupportedRates(vec![
+                Rate {
+                    rate: 2,
+                    basic: true
+                },
+                Rate {
+                    rate: 4,
+                    basic: true
+                },
+                Rate {
+                    rate: 12,
+                    basic: false
+                },
+            ])
+        );
+        assert_eq!(elements[2], InformationElement::DsParameter { channel: 6 });
+        assert_eq!(
+            elements[3],
+            InformationElement::Country(Country {
+                code: "US".to_string(),
+                environment: b' ',
+                triplets: vec![CountryTriplet {
+                    first_channel: 1,
+                    num_channels: 11,
+                    max_tx_power: 30,
+                }],
+            })
+        );
+        let InformationElement::Rsn(rsn) = &elements[4] else {
+            panic!("expected RSN element, got {:?}", elements[4]);
+        };
+        assert_eq!(rsn.group_cipher, CipherSuite::Ccmp);
+        assert_eq!(rsn.pairwise_ciphers, vec![CipherSuite::Ccmp]);
+        assert_eq!(rsn.akm_suites, vec![AkmSuite::Sae]);
+        assert!(rsn.mfp_required());
+        assert!(rsn.group_management_cipher.is_none());
+        let InformationElement::Wmm(wmm) = &elements[5] else {
+            panic!("expected WMM element, got {:?}", elements[5]);
+        };
+        assert!(wmm.uapsd());
+        assert_eq!(
+            elements[6],
+            InformationElement::Vendor {
+                oui: [0x00, 0x11, 0x22],
+                data: vec![0x33],
+            }
+        );
+        assert_eq!(
+            elements[7],
+            InformationElement::Unknown {
+                id: 255,
+                data: vec![0x6c, 0x01],
+            }
+        );
+        assert_eq!(
+            elements[8],
+            InformationElement::Unknown {
+                id: 42,
+                data: vec![0x00],
+            }
+        );
+    }
+
+    #[test]
+    fn parses_he_operation() {
+        let data = [
+            0x24, // extension ID
+            0x00, 0x00, 0x02, // parameters, with 6 GHz operation information
+            0x05, // BSS color
+            0xfc, 0xff, // basic MCS and NSS set
+            0x25, 0x02, 0x27, 0x00, 0x06, // 6 GHz operation information
+        ];
+        let InformationElement::HeOperation(he) = InformationElement::parse(255, &data) else {
+            panic!("expected HE operation element");
+        };
+        assert_eq!(he.bss_color(), 5);
+        assert!(he.vht_operation.is_none());
+        assert_eq!(
+            he.six_ghz_operation,
+            Some(SixGhzOperation {
+                primary_channel: 37,
+                control: 2,
+                center_freq_seg0: 39,
+                center_freq_seg1: 0,
+                min_rate: 6,
+            })
+        );
+    }
+}
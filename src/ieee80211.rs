@@ -0,0 +1,102 @@
+use std::fmt;
+
+/// IEEE 802.11 reason code, sent in deauthentication and disassociation frames and reported
+/// when a station disconnects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReasonCode {
+    Unspecified,
+    PrevAuthNotValid,
+    DeauthLeaving,
+    DisassocDueToInactivity,
+    DisassocApBusy,
+    Class2FrameFromNonauthSta,
+    Class3FrameFromNonassocSta,
+    DisassocStaHasLeft,
+    StaReqAssocWithoutAuth,
+    PwrCapabilityNotValid,
+    SupportedChannelNotValid,
+    BssTransitionDisassoc,
+    InvalidIe,
+    MichaelMicFailure,
+    FourwayHandshakeTimeout,
+    GroupKeyUpdateTimeout,
+    IeInFourwayDiffers,
+    GroupCipherNotValid,
+    PairwiseCipherNotValid,
+    AkmpNotValid,
+    UnsupportedRsnIeVersion,
+    InvalidRsnIeCapab,
+    Ieee8021xAuthFailed,
+    CipherSuiteRejected,
+    Other(u16),
+}
+
+impl ReasonCode {
+    pub fn code(&self) -> u16 {
+        match self {
+            ReasonCode::Unspecified => 1,
+            ReasonCode::PrevAuthNotValid => 2,
+            ReasonCode::DeauthLeaving => 3,
+            ReasonCode::DisassocDueToInactivity => 4,
+            ReasonCode::DisassocApBusy => 5,
+            ReasonCode::Class2FrameFromNonauthSta => 6,
+            ReasonCode::Class3FrameFromNonassocSta => 7,
+            ReasonCode::DisassocStaHasLeft => 8,
+            ReasonCode::StaReqAssocWithoutAuth => 9,
+            ReasonCode::PwrCapabilityNotValid => 10,
+            ReasonCode::SupportedChannelNotValid => 11,
+            ReasonCode::BssTransitionDisassoc => 12,
+            ReasonCode::InvalidIe => 13,
+            ReasonCode::MichaelMicFailure => 14,
+            ReasonCode::FourwayHandshakeTimeout => 15,
+            ReasonCode::GroupKeyUpdateTimeout => 16,
+            ReasonCode::IeInFourwayDiffers => 17,
+            ReasonCode::GroupCipherNotValid => 18,
+            ReasonCode::PairwiseCipherNotValid => 19,
+            ReasonCode::AkmpNotValid => 20,
+            ReasonCode::UnsupportedRsnIeVersion => 21,
+            ReasonCode::InvalidRsnIeCapab => 22,
+            ReasonCode::Ieee8021xAuthFailed => 23,
+            ReasonCode::CipherSuiteRejected => 24,
+            ReasonCode::Other(code) => *code,
+        }
+    }
+}
+
+impl From<u16> for ReasonCode {
+    fn from(code: u16) -> Self {
+        match code {
+            1 => ReasonCode::Unspecified,
+            2 => ReasonCode::PrevAuthNotValid,
+            3 => ReasonCode::DeauthLeaving,
+            4 => ReasonCode::DisassocDueToInactivity,
+            5 => ReasonCode::DisassocApBusy,
+            6 => ReasonCode::Class2FrameFromNonauthSta,
+            7 => ReasonCode::Class3FrameFromNonassocSta,
+            8 => ReasonCode::DisassocStaHasLeft,
+            9 => ReasonCode::StaReqAssocWithoutAuth,
+            10 => ReasonCode::PwrCapabilityNotValid,
+            11 => ReasonCode::SupportedChannelNotValid,
+            12 => ReasonCode::BssTransitionDisassoc,
+            13 => ReasonCode::InvalidIe,
+            14 => ReasonCode::MichaelMicFailure,
+            15 => ReasonCode::FourwayHandshakeTimeout,
+            16 => ReasonCode::GroupKeyUpdateTimeout,
+            17 => ReasonCode::IeInFourwayDiffers,
+            18 => ReasonCode::GroupCipherNotValid,
+            19 => ReasonCode::PairwiseCipherNotValid,
+            20 => ReasonCode::AkmpNotValid,
+            21 => ReasonCode::UnsupportedRsnIeVersion,
+            22 => ReasonCode::InvalidRsnIeCapab,
+            23 => ReasonCode::Ieee8021xAuthFailed,
+            24 => ReasonCode::CipherSuiteRejected,
+            other => ReasonCode::Other(other),
+        }
+    }
+}
+
+impl fmt::Display for ReasonCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.code())
+    }
+}
@@ -0,0 +1,53 @@
+use tokio::time::{Duration, Instant, Interval, MissedTickBehavior};
+
+/// Change in the health of the daemon, as observed by periodic pings
+pub(crate) enum Health {
+    Unresponsive,
+    Recovered,
+}
+
+/// Schedules the periodic pings and counts consecutive failures
+pub(crate) struct KeepAlive {
+    /// When to send the next ping. None if the health monitor is disabled.
+    interval: Option<Interval>,
+    /// Consecutive failures after which the daemon is considered unresponsive
+    failure_threshold: u32,
+    failures: u32,
+}
+
+impl KeepAlive {
+    pub(crate) fn new(period: Option<Duration>, failure_threshold: u32) -> Self {
+        let interval = period.map(|period| {
+            let mut interval = tokio::time::interval_at(Instant::now() + period, period);
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            interval
+        });
+        Self {
+            interval,
+            failure_threshold: failure_threshold.max(1),
+            failures: 0,
+        }
+    }
+
+    /// Resolves when the next ping is due. Never resolves if the health monitor is disabled.
+    pub(crate) async fn tick(&mut self) {
+        match &mut self.interval {
+            Some(interval) => {
+                interval.tick().await;
+            }
+            None => std::future::pending().await,
+        }
+    }
+
+    /// Records the outcome of a ping, returning the change in health if there is one.
+    pub(crate) fn record(&mut self, success: bool) -> Option<Health> {
+        if success {
+            let recovered = self.failures >= self.failure_threshold;
+            self.failures = 0;
+            recovered.then_some(Health::Recovered)
+        } else {
+            self.failures += 1;
+            (self.failures == self.failure_threshold).then_some(Health::Unresponsive)
+        }
+    }
+}
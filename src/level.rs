@@ -0,0 +1,54 @@
+use std::fmt;
+
+/// Priority of a message from wpa_supplicant or hostapd, sent as the `<level>` prefix of events.
+/// Levels are ordered from the most verbose to the most severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Level {
+    Excessive,
+    MsgDump,
+    Debug,
+    Info,
+    Warning,
+    Error,
+}
+
+impl Level {
+    /// The numeric value used by the daemons, eg: `3` for `MSG_INFO`
+    pub fn value(&self) -> u8 {
+        match self {
+            Level::Excessive => 0,
+            Level::MsgDump => 1,
+            Level::Debug => 2,
+            Level::Info => 3,
+            Level::Warning => 4,
+            Level::Error => 5,
+        }
+    }
+
+    pub fn from_value(value: u8) -> Option<Level> {
+        match value {
+            0 => Some(Level::Excessive),
+            1 => Some(Level::MsgDump),
+            2 => Some(Level::Debug),
+            3 => Some(Level::Info),
+            4 => Some(Level::Warning),
+            5 => Some(Level::Error),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    /// The name used by the `LOG_LEVEL` command
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Level::Excessive => "EXCESSIVE",
+            Level::MsgDump => "MSGDUMP",
+            Level::Debug => "DEBUG",
+            Level::Info => "INFO",
+            Level::Warning => "WARNING",
+            Level::Error => "ERROR",
+        };
+        write!(f, "{name}")
+    }
+}
@@ -20,11 +20,26 @@ use tokio::sync::{broadcast, mpsc, oneshot};
 pub mod ap;
 /// Crate-wide error types
 pub mod error;
+/// Frequency, channel and band types shared by both runtimes
+pub mod frequency;
+/// IEEE 802.11 information element parsing
+pub mod ie;
+/// IEEE 802.11 codes shared by both runtimes
+pub mod ieee80211;
+/// Priority levels of messages from wpa_supplicant and hostapd
+pub mod level;
+/// MAC address type shared by both runtimes
+pub mod mac;
 /// WiFi Station (network client) runtime and types
 pub mod sta;
 
+pub(crate) mod keep_alive;
+pub(crate) mod parsing;
 pub(crate) mod socket_handle;
 
+pub use frequency::{Band, Channel, ChannelWidth, Frequency};
+pub use level::Level;
+pub use mac::MacAddr;
 use socket_handle::SocketHandle;
 pub type Result<T = ()> = std::result::Result<T, error::Error>;
 
@@ -0,0 +1,47 @@
+use super::*;
+use serde::{Serialize, Serializer};
+use std::fmt;
+
+/// A MAC address (eg: a BSSID or the address of a station), as reported by hostapd and
+/// wpa_supplicant in the `aa:bb:cc:dd:ee:ff` format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct MacAddr(pub [u8; 6]);
+
+impl MacAddr {
+    pub fn octets(&self) -> [u8; 6] {
+        self.0
+    }
+}
+
+impl FromStr for MacAddr {
+    type Err = error::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut octets = [0; 6];
+        let mut split = s.trim().split(':');
+        for octet in octets.iter_mut() {
+            *octet = split
+                .next()
+                .filter(|hex| hex.len() == 2)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                .ok_or_else(|| error::Error::InvalidMacAddress(s.into()))?;
+        }
+        if split.next().is_some() {
+            return Err(error::Error::InvalidMacAddress(s.into()));
+        }
+        Ok(MacAddr(octets))
+    }
+}
+
+impl fmt::Display for MacAddr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let [a, b, c, d, e, g] = self.0;
+        write!(f, "{a:02x}:{b:02x}:{c:02x}:{d:02x}:{e:02x}:{g:02x}")
+    }
+}
+
+impl Serialize for MacAddr {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
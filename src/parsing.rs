@@ -0,0 +1,168 @@
+use crate::Level;
+use log::warn;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Splits an event into its `<level>` prefix and the rest of the message, dropping the
+/// `IFNAME=<ifname> ` prefix of the global control interface.
+pub(crate) fn split_level(msg: &str) -> (Option<Level>, &str) {
+    let rest = match msg.strip_prefix("IFNAME=") {
+        Some(rest) => rest.split_once(' ').map_or(rest, |(_, rest)| rest),
+        None => msg,
+    };
+    match rest.strip_prefix('<').and_then(|rest| rest.split_once('>')) {
+        Some((level, rest)) if !level.is_empty() && level.chars().all(|c| c.is_ascii_digit()) => {
+            (level.parse().ok().and_then(Level::from_value), rest)
+        }
+        _ => (None, msg),
+    }
+}
+
+/// Whether a message is an unsolicited event rather than the response to a request. Events carry
+/// a `<level>` prefix.
+pub(crate) fn is_unsolicited(msg: &str) -> bool {
+    split_level(msg).1.len() != msg.len()
+}
+
+/// Finds the value of `key=value` in an event, where the value may be a double quoted string
+/// escaped by wpa_supplicant's `printf_encode`.
+pub(crate) fn event_field(event: &str, key: &str) -> Option<String> {
+    let pattern = format!("{key}=");
+    // make sure we match a whole key and not the end of another one
+    let (index, _) = event.match_indices(&pattern).find(|(index, _)| {
+        event[..*index]
+            .chars()
+            .last()
+            .is_none_or(|c| c.is_whitespace() || c == '[')
+    })?;
+    let value = &event[index + pattern.len()..];
+    if let Some(quoted) = value.strip_prefix('"') {
+        Some(printf_decode(quoted))
+    } else {
+        Some(
+            value
+                .split_whitespace()
+                .next()
+                .unwrap_or_default()
+                .to_string(),
+        )
+    }
+}
+
+/// Decodes a string escaped by wpa_supplicant's `printf_encode`, up to its closing double quote.
+/// The decoded bytes are not necessarily UTF-8, eg: for SSIDs, so invalid sequences are replaced.
+fn printf_decode(escaped: &str) -> String {
+    let mut result = Vec::new();
+    let mut bytes = escaped.bytes().peekable();
+    while let Some(b) = bytes.next() {
+        match b {
+            b'"' => break,
+            b'\\' => match bytes.next() {
+                Some(b'n') => result.push(b'\n'),
+                Some(b'r') => result.push(b'\r'),
+                Some(b't') => result.push(b'\t'),
+                Some(b'e') => result.push(0x1b),
+                Some(b'x') => result.push(take_digits(&mut bytes, 16, 2, 0)),
+                Some(digit @ b'0'..=b'7') => {
+                    result.push(take_digits(&mut bytes, 8, 2, u32::from(digit - b'0')))
+                }
+                Some(b) => result.push(b),
+                None => (),
+            },
+            b => result.push(b),
+        }
+    }
+    String::from_utf8_lossy(&result).into_owned()
+}
+
+/// Parses up to `max` more digits of an escaped byte, following the digits already parsed into
+/// `value`
+fn take_digits(
+    bytes: &mut std::iter::Peekable<std::str::Bytes>,
+    radix: u32,
+    max: usize,
+    mut value: u32,
+) -> u8 {
+    for _ in 0..max {
+        let Some(digit) = bytes.peek().and_then(|&b| char::from(b).to_digit(radix)) else {
+            break;
+        };
+        value = value * radix + digit;
+        bytes.next();
+    }
+    value as u8
+}
+
+/// Removes and parses a field of a `key=value` response, keeping it in the map if it cannot be
+/// parsed.
+pub(crate) fn take_parsed<T: FromStr>(
+    fields: &mut HashMap<String, String>,
+    key: &str,
+) -> Option<T> {
+    take_parsed_with(fields, key, T::from_str)
+}
+
+/// Like `take_parsed`, for fields which need a parser other than `FromStr`, eg: hex numbers.
+pub(crate) fn take_parsed_with<T, E>(
+    fields: &mut HashMap<String, String>,
+    key: &str,
+    parse: impl FnOnce(&str) -> Result<T, E>,
+) -> Option<T> {
+    let value = fields.get(key)?;
+    match parse(value) {
+        Ok(parsed) => {
+            fields.remove(key);
+            Some(parsed)
+        }
+        Err(_) => {
+            warn!("Invalid value for {key}: {value}");
+            None
+        }
+    }
+}
+
+/// Removes and decodes a hex encoded field, keeping it in the map if it cannot be decoded.
+pub(crate) fn take_hex(fields: &mut HashMap<String, String>, key: &str) -> Vec<u8> {
+    let bytes = take_parsed_with(fields, key, |hex| {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| {
+                hex.get(i..i + 2)
+                    .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+            })
+            .collect::<Option<Vec<u8>>>()
+            .ok_or(())
+    });
+    bytes.unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_event_fields() {
+        let cases = [
+            ("id=0 ssid=home", "ssid", Some("home")),
+            ("id=0 bssid=02:00:00:00:01:00", "id", Some("0")),
+            ("bssid=02:00:00:00:01:00", "ssid", None),
+            ("id=3 id_str=", "id", Some("3")),
+            ("id=3 id_str=", "id_str", Some("")),
+            (r#"ssid="home" reason=WRONG_KEY"#, "ssid", Some("home")),
+            (r#"ssid="my home""#, "ssid", Some("my home")),
+            (r#"ssid="a\"b\\c""#, "ssid", Some(r#"a"b\c"#)),
+            (r#"ssid="a\nb\tc\rd\ee""#, "ssid", Some("a\nb\tc\rd\x1be")),
+            (r#"ssid="caf\xc3\xa9""#, "ssid", Some("café")),
+            (r#"ssid="\x41\x4""#, "ssid", Some("A\x04")),
+            (r#"ssid="\101\7x""#, "ssid", Some("A\x07x")),
+            (r#"ssid="\xff""#, "ssid", Some("\u{fffd}")),
+        ];
+        for (event, key, expected) in cases {
+            assert_eq!(
+                event_field(event, key).as_deref(),
+                expected,
+                "finding {key} in {event}"
+            );
+        }
+    }
+}
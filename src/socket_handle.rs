@@ -1,19 +1,34 @@
 use super::*;
+use crate::parsing::is_unsolicited;
 use std::io::ErrorKind;
 use tokio::net::UnixDatagram;
+use tokio::time::Duration;
 
-pub struct SocketHandle<const N: usize> {
+pub struct SocketHandle {
     #[allow(unused)]
     /// Temporary directory for socket. If it drops, socket breaks.
     tmp_dir: tempfile::TempDir,
     /// Socket for synchronous messages
     pub socket: UnixDatagram,
-    pub buffer: [u8; N],
+    /// Receive buffer, sized to hold a message of the maximum size
+    buffer: Vec<u8>,
+    /// Size of the largest message which can be received without failing with
+    /// `Error::ResponseTruncated`
+    pub max_response_size: usize,
+    /// How long to wait for the response to a request
+    pub timeout: Duration,
+    /// Where unsolicited messages received while waiting for a response are forwarded
+    pub(crate) unsolicited_sender: Option<mpsc::UnboundedSender<String>>,
+    /// Set once a request fails with an I/O error, which means the daemon is gone, eg: after it
+    /// crashed without sending `CTRL-EVENT-TERMINATING`
+    pub(crate) connection_lost: bool,
 }
 
 const RETRY_MINUTES: u64 = 5;
+pub(crate) const DEFAULT_TIMEOUT: Duration = Duration::from_secs(1);
+pub(crate) const DEFAULT_MAX_RESPONSE_SIZE: usize = 64 * 1024;
 
-impl<const N: usize> SocketHandle<N> {
+impl SocketHandle {
     pub(crate) async fn open<P, S>(
         path: P,
         label: &str,
@@ -78,44 +93,114 @@ impl<const N: usize> SocketHandle<N> {
             Self {
                 tmp_dir,
                 socket: socket?,
-                buffer: [0; N],
+                buffer: Vec::new(),
+                max_response_size: DEFAULT_MAX_RESPONSE_SIZE,
+                timeout: DEFAULT_TIMEOUT,
+                unsolicited_sender: None,
+                connection_lost: false,
             },
             deferred_requests,
         ))
     }
 
-    pub async fn command(&mut self, cmd: &[u8]) -> Result {
+    /// Sends a request and returns the response, waiting for it up to the handle's timeout.
+    pub async fn request(&mut self, cmd: &[u8]) -> Result<String> {
+        let response = self.send_request(cmd).await;
+        if let Err(error::Error::Io(e)) = &response {
+            warn!("Request socket failed: {e}");
+            self.connection_lost = true;
+        }
+        response
+    }
+
+    async fn send_request(&mut self, cmd: &[u8]) -> Result<String> {
+        // a response arriving after its request timed out would otherwise be taken as the
+        // response to this request
+        self.resize_buffer();
+        while let Ok(n) = self.socket.try_recv(&mut self.buffer) {
+            match self.decode(n) {
+                Ok(msg) if self.forward_unsolicited(&msg) => (),
+                Ok(msg) => warn!("Discarding stale response: {msg}"),
+                Err(e) => warn!("Discarding stale response: {e}"),
+            }
+        }
         let n = self.socket.send(cmd).await?;
         if n != cmd.len() {
             return Err(error::Error::DidNotWriteAllBytes(n, cmd.len()));
         }
-        self.expect_ok_with_default_timeout().await
+        // events may arrive before the response, keep waiting for it until the deadline
+        let deadline = tokio::time::Instant::now() + self.timeout;
+        loop {
+            let n = tokio::time::timeout_at(deadline, self.socket.recv(&mut self.buffer))
+                .await
+                .map_err(|_| error::Error::Timeout)??;
+            match self.decode(n) {
+                Ok(response) if self.forward_unsolicited(&response) => (),
+                Ok(response) => return Ok(response),
+                // an event which is truncated or not UTF-8 must not fail the request
+                Err(e) if is_unsolicited(&String::from_utf8_lossy(&self.buffer[..n])) => {
+                    warn!("Dropping unsolicited message: {e}");
+                }
+                Err(e) => return Err(e),
+            }
+        }
     }
 
-    async fn expect_ok(&mut self) -> Result {
-        match self.socket.recv(&mut self.buffer).await {
-            Ok(n) => {
-                let data_str = std::str::from_utf8(&self.buffer[..n])?.trim_end();
-                if data_str.trim() == "OK" {
-                    Ok(())
-                } else {
-                    Err(error::Error::UnexpectedWifiApRepsonse(data_str.into()))
-                }
+    /// Receives a message, failing if it is larger than `max_response_size`.
+    pub async fn recv(&mut self) -> Result<String> {
+        self.resize_buffer();
+        let n = self.socket.recv(&mut self.buffer).await?;
+        self.decode(n)
+    }
+
+    /// Datagrams larger than the buffer are silently truncated, so the buffer holds one byte more
+    /// than the maximum size to tell a message of exactly that size from a truncated one.
+    fn resize_buffer(&mut self) {
+        self.buffer.resize(self.max_response_size + 1, 0);
+    }
+
+    fn decode(&self, n: usize) -> Result<String> {
+        if n > self.max_response_size {
+            return Err(error::Error::ResponseTruncated(self.max_response_size));
+        }
+        Ok(std::str::from_utf8(&self.buffer[..n])?
+            .trim_end()
+            .to_string())
+    }
+
+    /// Forwards the message to the event stream if it is an unsolicited event, returning whether
+    /// it was one.
+    fn forward_unsolicited(&self, msg: &str) -> bool {
+        if !is_unsolicited(msg) {
+            return false;
+        }
+        debug!("Unsolicited message on request socket: {msg}");
+        if let Some(sender) = &self.unsolicited_sender {
+            if sender.send(msg.to_string()).is_err() {
+                warn!("Event stream closed, dropping unsolicited message: {msg}");
             }
-            Err(e) => Err(error::Error::UnsolicitedIoError(e)),
         }
+        true
     }
 
-    async fn expect_ok_with_default_timeout(&mut self) -> Result {
-        self.expect_ok_with_timeout(tokio::time::Duration::from_secs(1))
-            .await
+    /// Sends `PING`, returning the round-trip time of the `PONG` response.
+    pub async fn ping(&mut self) -> Result<Duration> {
+        let start = tokio::time::Instant::now();
+        let response = self.request(b"PING").await?;
+        if response == "PONG" {
+            Ok(start.elapsed())
+        } else {
+            Err(error::Error::UnexpectedWifiApRepsonse(response))
+        }
     }
 
-    pub async fn expect_ok_with_timeout(&mut self, timeout: tokio::time::Duration) -> Result {
-        tokio::select!(
-            resp = self.expect_ok() => resp,
-            _ =
-                tokio::time::sleep(timeout) => Err(error::Error::Timeout)
-        )
+    /// Sends a request expecting `OK` as a response.
+    pub async fn command(&mut self, cmd: &[u8]) -> Result {
+        let response = self.request(cmd).await?;
+        if response == "OK" {
+            Ok(())
+        } else {
+            Err(error::Error::UnexpectedWifiApRepsonse(response))
+        }
     }
 }
@@ -4,6 +4,19 @@ use super::*;
 /// scan, the result will be shared between them.
 pub type ScanResults = Arc<Vec<ScanResult>>;
 
+/// Scan results along with the time they were received
+#[derive(Debug, Clone)]
+pub struct ScanSnapshot {
+    pub results: ScanResults,
+    pub timestamp: Instant,
+}
+
+impl ScanSnapshot {
+    pub fn age(&self) -> Duration {
+        self.timestamp.elapsed()
+    }
+}
+
 #[derive(Debug)]
 /// Result from selecting a network, including a success or a specific failure (eg: incorect psk).
 /// Timeout does not necessarily mean failure; it only means that we did not received a parseable response.
@@ -35,6 +48,34 @@ impl fmt::Display for SelectResult {
     }
 }
 
+#[derive(Debug)]
+/// Result from a WPS enrollment (push button or PIN). On success, the id of the network that
+/// wpa_supplicant created from the received credentials is provided.
+pub enum WpsResult {
+    Success(usize),
+    Overlap,
+    Timeout,
+    Fail { config_error: Option<u16> },
+    Cancelled,
+    PendingWps,
+}
+
+impl fmt::Display for WpsResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WpsResult::Success(id) => write!(f, "success (network {id})"),
+            WpsResult::Overlap => write!(f, "pbc_overlap"),
+            WpsResult::Timeout => write!(f, "wps_timeout"),
+            WpsResult::Fail {
+                config_error: Some(config_error),
+            } => write!(f, "wps_fail (config_error={config_error})"),
+            WpsResult::Fail { config_error: None } => write!(f, "wps_fail"),
+            WpsResult::Cancelled => write!(f, "wps_cancelled"),
+            WpsResult::PendingWps => write!(f, "wps_already_pending"),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub(crate) enum RemoveNetwork {
     Id(usize),
@@ -44,21 +85,41 @@ pub(crate) enum RemoveNetwork {
 #[derive(Debug)]
 pub(crate) enum Request {
     Custom(String, oneshot::Sender<Result<String>>),
-    Status(oneshot::Sender<Result<Status>>),
+    Status(oneshot::Sender<Result<StationStatus>>),
     Networks(oneshot::Sender<Result<Vec<NetworkResult>>>),
-    Scan(oneshot::Sender<Result<ScanResults>>),
+    Bss(MacAddr, oneshot::Sender<Result<BssInfo>>),
+    AllBss(oneshot::Sender<Result<Vec<BssInfo>>>),
+    Scan(ScanRequest, oneshot::Sender<Result<ScanSnapshot>>),
+    CachedScan(Duration, oneshot::Sender<Result<ScanSnapshot>>),
     AddNetwork(oneshot::Sender<Result<usize>>),
     SetNetwork(usize, SetNetwork, oneshot::Sender<Result>),
     SaveConfig(oneshot::Sender<Result>),
     RemoveNetwork(RemoveNetwork, oneshot::Sender<Result>),
     SelectNetwork(usize, oneshot::Sender<Result<SelectResult>>),
+    Wps(Wps, oneshot::Sender<Result<WpsResult>>),
+    WpsCancel(oneshot::Sender<Result>),
+    CredentialResponse(CredentialResponse, oneshot::Sender<Result>),
+    Ping(oneshot::Sender<Result<Duration>>),
+    SetLogLevel(Level, oneshot::Sender<Result>),
+    SetSaePwe(SaePwe, oneshot::Sender<Result>),
     Shutdown,
+    /// Handles the inner request with a timeout other than the default one
+    WithTimeout(Duration, Box<Request>),
     SelectTimeout,
+    WpsTimeout,
+    /// Retries the scan which wpa_supplicant was too busy to start, unless a later retry was
+    /// scheduled since
+    ScanRetry(u64),
+    /// Fails the scan with the given queue ID if it has not completed yet
+    ScanTimeout(u64),
 }
 
 impl ShutdownSignal for Request {
     fn is_shutdown(&self) -> bool {
-        matches!(self, Request::Shutdown)
+        match self {
+            Request::WithTimeout(_, request) => request.is_shutdown(),
+            request => matches!(request, Request::Shutdown),
+        }
     }
     fn inform_of_shutdown(self) {
         match self {
@@ -68,10 +129,19 @@ impl ShutdownSignal for Request {
             Request::Status(response) => {
                 let _ = response.send(Err(error::Error::StartupAborted));
             }
+            Request::Bss(_, response) => {
+                let _ = response.send(Err(error::Error::StartupAborted));
+            }
+            Request::AllBss(response) => {
+                let _ = response.send(Err(error::Error::StartupAborted));
+            }
             Request::Networks(response) => {
                 let _ = response.send(Err(error::Error::StartupAborted));
             }
-            Request::Scan(response) => {
+            Request::Scan(_, response) => {
+                let _ = response.send(Err(error::Error::StartupAborted));
+            }
+            Request::CachedScan(_, response) => {
                 let _ = response.send(Err(error::Error::StartupAborted));
             }
             Request::AddNetwork(response) => {
@@ -89,32 +159,139 @@ impl ShutdownSignal for Request {
             Request::SelectNetwork(_, response) => {
                 let _ = response.send(Err(error::Error::StartupAborted));
             }
+            Request::Wps(_, response) => {
+                let _ = response.send(Err(error::Error::StartupAborted));
+            }
+            Request::WpsCancel(response) => {
+                let _ = response.send(Err(error::Error::StartupAborted));
+            }
+            Request::CredentialResponse(_, response) => {
+                let _ = response.send(Err(error::Error::StartupAborted));
+            }
+            Request::Ping(response) => {
+                let _ = response.send(Err(error::Error::StartupAborted));
+            }
+            Request::SetLogLevel(_, response) => {
+                let _ = response.send(Err(error::Error::StartupAborted));
+            }
+            Request::SetSaePwe(_, response) => {
+                let _ = response.send(Err(error::Error::StartupAborted));
+            }
             Request::Shutdown => {}
+            Request::WithTimeout(_, request) => request.inform_of_shutdown(),
             Request::SelectTimeout => {}
+            Request::WpsTimeout => {}
+            Request::ScanRetry(_) => {}
+            Request::ScanTimeout(_) => {}
         }
     }
 }
 
-#[derive(Debug)]
 pub(crate) enum SetNetwork {
     Ssid(String),
     Bssid(String),
     Psk(String),
-    KeyMgmt(KeyMgmt),
+    KeyMgmt(Vec<KeyMgmt>),
+    SaePassword(String),
+    Ieee80211w(Pmf),
+    Eap(Vec<EapMethod>),
+    Identity(String),
+    AnonymousIdentity(String),
+    Password(String),
+    Phase1(String),
+    Phase2(String),
+    CaCert(String),
+    ClientCert(String),
+    PrivateKey(String),
+    PrivateKeyPasswd(String),
+    DomainSuffixMatch(String),
+}
+
+impl std::fmt::Debug for SetNetwork {
+    // requests are logged, so credentials are kept out of the debug output
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SetNetwork::Ssid(ssid) => f.debug_tuple("Ssid").field(ssid).finish(),
+            SetNetwork::Bssid(bssid) => f.debug_tuple("Bssid").field(bssid).finish(),
+            SetNetwork::Psk(_) => f.write_str("Psk(<redacted>)"),
+            SetNetwork::KeyMgmt(mgmt) => f.debug_tuple("KeyMgmt").field(mgmt).finish(),
+            SetNetwork::SaePassword(_) => f.write_str("SaePassword(<redacted>)"),
+            SetNetwork::Ieee80211w(pmf) => f.debug_tuple("Ieee80211w").field(pmf).finish(),
+            SetNetwork::Eap(methods) => f.debug_tuple("Eap").field(methods).finish(),
+            SetNetwork::Identity(identity) => f.debug_tuple("Identity").field(identity).finish(),
+            SetNetwork::AnonymousIdentity(identity) => {
+                f.debug_tuple("AnonymousIdentity").field(identity).finish()
+            }
+            SetNetwork::Password(_) => f.write_str("Password(<redacted>)"),
+            SetNetwork::Phase1(phase1) => f.debug_tuple("Phase1").field(phase1).finish(),
+            SetNetwork::Phase2(phase2) => f.debug_tuple("Phase2").field(phase2).finish(),
+            SetNetwork::CaCert(path) => f.debug_tuple("CaCert").field(path).finish(),
+            SetNetwork::ClientCert(path) => f.debug_tuple("ClientCert").field(path).finish(),
+            SetNetwork::PrivateKey(path) => f.debug_tuple("PrivateKey").field(path).finish(),
+            SetNetwork::PrivateKeyPasswd(_) => f.write_str("PrivateKeyPasswd(<redacted>)"),
+            SetNetwork::DomainSuffixMatch(domain) => {
+                f.debug_tuple("DomainSuffixMatch").field(domain).finish()
+            }
+        }
+    }
+}
+
+pub(crate) struct CredentialResponse {
+    pub network_id: usize,
+    pub field: CredentialField,
+    pub value: String,
+}
+
+impl std::fmt::Debug for CredentialResponse {
+    // requests are logged, so the credential itself is kept out of the debug output
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CredentialResponse")
+            .field("network_id", &self.network_id)
+            .field("field", &self.field)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug)]
+pub(crate) enum Wps {
+    /// Push button, optionally restricted to a single AP
+    Pbc(Option<MacAddr>),
+    /// PIN enrollment, optionally restricted to a single AP
+    Pin(Option<MacAddr>, String),
 }
 
 #[derive(Clone)]
 /// Request client wraps the request events, awaiting oneshot channels when appropriate
 pub struct RequestClient {
     sender: mpsc::Sender<Request>,
+    /// Overrides the default request timeout for requests made through this client
+    timeout: Option<Duration>,
 }
 
 impl RequestClient {
     pub(crate) fn new(sender: mpsc::Sender<Request>) -> RequestClient {
-        RequestClient { sender }
+        RequestClient {
+            sender,
+            timeout: None,
+        }
+    }
+
+    /// Returns a client whose requests wait up to `timeout` for each response from the daemon
+    /// instead of the default request timeout.
+    pub fn with_timeout(&self, timeout: Duration) -> RequestClient {
+        RequestClient {
+            sender: self.sender.clone(),
+            timeout: Some(timeout),
+        }
     }
 
     async fn send_request(&self, request: Request) -> Result {
+        let request = match self.timeout {
+            Some(timeout) if !request.is_shutdown() => {
+                Request::WithTimeout(timeout, Box::new(request))
+            }
+            _ => request,
+        };
         self.sender
             .send(request)
             .await
@@ -128,9 +305,27 @@ impl RequestClient {
         request.await?
     }
 
+    /// Scans every channel for any network
     pub async fn get_scan(&self) -> Result<Arc<Vec<ScanResult>>> {
+        self.scan(ScanRequest::default()).await
+    }
+
+    /// Scans with the given parameters. Scans run one at a time, and the results are the ones of
+    /// a scan started after this request: callers requesting the same parameters while a scan is
+    /// waiting to start share its results.
+    pub async fn scan(&self, scan_request: ScanRequest) -> Result<ScanResults> {
         let (response, request) = oneshot::channel();
-        self.send_request(Request::Scan(response)).await?;
+        self.send_request(Request::Scan(scan_request, response))
+            .await?;
+        Ok(request.await??.results)
+    }
+
+    /// The results of the last scan of every channel if they are at most `max_age` old,
+    /// otherwise the results of a new one
+    pub async fn get_cached_scan(&self, max_age: Duration) -> Result<ScanSnapshot> {
+        let (response, request) = oneshot::channel();
+        self.send_request(Request::CachedScan(max_age, response))
+            .await?;
         request.await?
     }
 
@@ -140,7 +335,22 @@ impl RequestClient {
         request.await?
     }
 
-    pub async fn get_status(&self) -> Result<Status> {
+    /// Details about a BSS seen while scanning, including its information elements. Fails with
+    /// `Error::BssNotFound` if the BSS is not in wpa_supplicant's BSS table.
+    pub async fn get_bss(&self, bssid: MacAddr) -> Result<BssInfo> {
+        let (response, request) = oneshot::channel();
+        self.send_request(Request::Bss(bssid, response)).await?;
+        request.await?
+    }
+
+    /// Details about every BSS in wpa_supplicant's BSS table
+    pub async fn get_all_bss(&self) -> Result<Vec<BssInfo>> {
+        let (response, request) = oneshot::channel();
+        self.send_request(Request::AllBss(response)).await?;
+        request.await?
+    }
+
+    pub async fn get_status(&self) -> Result<StationStatus> {
         let (response, request) = oneshot::channel();
         self.send_request(Request::Status(response)).await?;
         request.await?
@@ -152,48 +362,121 @@ impl RequestClient {
         request.await?
     }
 
-    pub async fn set_network_psk(&self, network_id: usize, psk: String) -> Result {
+    async fn set_network(&self, network_id: usize, param: SetNetwork) -> Result {
         let (response, request) = oneshot::channel();
-        self.send_request(Request::SetNetwork(
-            network_id,
-            SetNetwork::Psk(psk),
-            response,
-        ))
-        .await?;
+        self.send_request(Request::SetNetwork(network_id, param, response))
+            .await?;
         request.await?
     }
 
+    pub async fn set_network_psk(&self, network_id: usize, psk: String) -> Result {
+        self.set_network(network_id, SetNetwork::Psk(psk)).await
+    }
+
     pub async fn set_network_ssid(&self, network_id: usize, ssid: String) -> Result {
-        let (response, request) = oneshot::channel();
-        self.send_request(Request::SetNetwork(
-            network_id,
-            SetNetwork::Ssid(ssid),
-            response,
-        ))
-        .await?;
-        request.await?
+        self.set_network(network_id, SetNetwork::Ssid(ssid)).await
     }
 
     pub async fn set_network_bssid(&self, network_id: usize, bssid: String) -> Result {
-        let (response, request) = oneshot::channel();
-        self.send_request(Request::SetNetwork(
-            network_id,
-            SetNetwork::Bssid(bssid),
-            response,
-        ))
-        .await?;
-        request.await?
+        self.set_network(network_id, SetNetwork::Bssid(bssid)).await
     }
 
     pub async fn set_network_keymgmt(&self, network_id: usize, mgmt: KeyMgmt) -> Result {
-        let (response, request) = oneshot::channel();
-        self.send_request(Request::SetNetwork(
-            network_id,
-            SetNetwork::KeyMgmt(mgmt),
-            response,
-        ))
-        .await?;
-        request.await?
+        self.set_network(network_id, SetNetwork::KeyMgmt(vec![mgmt]))
+            .await
+    }
+
+    /// Allow several key management types on the network, eg: `WpaPsk` and `Sae` for a
+    /// WPA2/WPA3-Personal transition network.
+    pub async fn set_network_keymgmts(&self, network_id: usize, mgmt: Vec<KeyMgmt>) -> Result {
+        self.set_network(network_id, SetNetwork::KeyMgmt(mgmt))
+            .await
+    }
+
+    /// Password used for SAE (WPA3-Personal). If not set, SAE uses the `psk` passphrase.
+    pub async fn set_network_sae_password(&self, network_id: usize, password: String) -> Result {
+        self.set_network(network_id, SetNetwork::SaePassword(password))
+            .await
+    }
+
+    pub async fn set_network_ieee80211w(&self, network_id: usize, pmf: Pmf) -> Result {
+        self.set_network(network_id, SetNetwork::Ieee80211w(pmf))
+            .await
+    }
+
+    /// Set the accepted EAP methods of the network. If omitted, wpa_supplicant allows all
+    /// methods it was built with.
+    pub async fn set_network_eap(&self, network_id: usize, methods: Vec<EapMethod>) -> Result {
+        self.set_network(network_id, SetNetwork::Eap(methods)).await
+    }
+
+    pub async fn set_network_identity(&self, network_id: usize, identity: String) -> Result {
+        self.set_network(network_id, SetNetwork::Identity(identity))
+            .await
+    }
+
+    /// Identity sent in the unencrypted phase of tunneled EAP methods (eg: PEAP, TTLS).
+    pub async fn set_network_anonymous_identity(
+        &self,
+        network_id: usize,
+        identity: String,
+    ) -> Result {
+        self.set_network(network_id, SetNetwork::AnonymousIdentity(identity))
+            .await
+    }
+
+    pub async fn set_network_password(&self, network_id: usize, password: String) -> Result {
+        self.set_network(network_id, SetNetwork::Password(password))
+            .await
+    }
+
+    /// Outer authentication parameters (eg: `peapver=0` or `tls_disable_tlsv1_0=1`).
+    pub async fn set_network_phase1(&self, network_id: usize, phase1: String) -> Result {
+        self.set_network(network_id, SetNetwork::Phase1(phase1))
+            .await
+    }
+
+    /// Inner authentication parameters (eg: `auth=MSCHAPV2`).
+    pub async fn set_network_phase2(&self, network_id: usize, phase2: String) -> Result {
+        self.set_network(network_id, SetNetwork::Phase2(phase2))
+            .await
+    }
+
+    /// Path to the CA certificate(s) used to validate the authentication server.
+    pub async fn set_network_ca_cert(&self, network_id: usize, path: String) -> Result {
+        self.set_network(network_id, SetNetwork::CaCert(path)).await
+    }
+
+    /// Path to the client certificate, for EAP-TLS.
+    pub async fn set_network_client_cert(&self, network_id: usize, path: String) -> Result {
+        self.set_network(network_id, SetNetwork::ClientCert(path))
+            .await
+    }
+
+    /// Path to the client private key, for EAP-TLS.
+    pub async fn set_network_private_key(&self, network_id: usize, path: String) -> Result {
+        self.set_network(network_id, SetNetwork::PrivateKey(path))
+            .await
+    }
+
+    pub async fn set_network_private_key_passwd(
+        &self,
+        network_id: usize,
+        password: String,
+    ) -> Result {
+        self.set_network(network_id, SetNetwork::PrivateKeyPasswd(password))
+            .await
+    }
+
+    /// Only accept authentication servers whose certificate matches this domain (or a
+    /// subdomain of it).
+    pub async fn set_network_domain_suffix_match(
+        &self,
+        network_id: usize,
+        domain: String,
+    ) -> Result {
+        self.set_network(network_id, SetNetwork::DomainSuffixMatch(domain))
+            .await
     }
 
     pub async fn save_config(&self) -> Result {
@@ -223,6 +506,77 @@ impl RequestClient {
         request.await?
     }
 
+    /// Start WPS push button enrollment. If `bssid` is `None`, any AP with an active push button
+    /// will be used. Resolves once wpa_supplicant reports the outcome of the enrollment.
+    pub async fn wps_pbc(&self, bssid: Option<MacAddr>) -> Result<WpsResult> {
+        let (response, request) = oneshot::channel();
+        self.send_request(Request::Wps(Wps::Pbc(bssid), response))
+            .await?;
+        request.await?
+    }
+
+    /// Start WPS PIN enrollment using the provided PIN. If `bssid` is `None`, any AP will be used.
+    /// Resolves once wpa_supplicant reports the outcome of the enrollment.
+    pub async fn wps_pin(&self, bssid: Option<MacAddr>, pin: String) -> Result<WpsResult> {
+        let (response, request) = oneshot::channel();
+        self.send_request(Request::Wps(Wps::Pin(bssid, pin), response))
+            .await?;
+        request.await?
+    }
+
+    /// Cancel an ongoing WPS operation. A pending `wps_pbc` or `wps_pin` call will resolve with
+    /// `WpsResult::Cancelled`.
+    pub async fn wps_cancel(&self) -> Result {
+        let (response, request) = oneshot::channel();
+        self.send_request(Request::WpsCancel(response)).await?;
+        request.await?
+    }
+
+    /// Answer a `Broadcast::CredentialRequest` by providing the requested credential for the
+    /// network (`CTRL-RSP-<field>-<network id>`).
+    pub async fn respond_credential(
+        &self,
+        network_id: usize,
+        field: CredentialField,
+        value: String,
+    ) -> Result {
+        let (response, request) = oneshot::channel();
+        self.send_request(Request::CredentialResponse(
+            CredentialResponse {
+                network_id,
+                field,
+                value,
+            },
+            response,
+        ))
+        .await?;
+        request.await?
+    }
+
+    /// Checks that wpa_supplicant is responsive, returning the round-trip time of the request.
+    pub async fn ping(&self) -> Result<Duration> {
+        let (response, request) = oneshot::channel();
+        self.send_request(Request::Ping(response)).await?;
+        request.await?
+    }
+
+    /// Sets the level of the debug output of wpa_supplicant. Events below the minimum event level
+    /// configured in the setup are not broadcast regardless of this level.
+    pub async fn set_log_level(&self, level: Level) -> Result {
+        let (response, request) = oneshot::channel();
+        self.send_request(Request::SetLogLevel(level, response))
+            .await?;
+        request.await?
+    }
+
+    /// Sets how the SAE password element is derived. This is a global setting of wpa_supplicant
+    /// rather than a network parameter, and applies to every SAE network.
+    pub async fn set_sae_pwe(&self, pwe: SaePwe) -> Result {
+        let (response, request) = oneshot::channel();
+        self.send_request(Request::SetSaePwe(pwe, response)).await?;
+        request.await?
+    }
+
     pub async fn shutdown(&self) -> Result {
         self.send_request(Request::Shutdown).await?;
         Ok(())
@@ -232,14 +586,58 @@ impl RequestClient {
 /// Broadcast events are unexpected, such as losing connection to the host network.
 #[derive(Debug, Clone)]
 pub enum Broadcast {
-    Connected,
-    Disconnected,
+    Connected(ConnectionInfo),
+    Disconnected(DisconnectionInfo),
     NetworkNotFound,
     WrongPsk,
+    AssocReject(RejectInfo),
+    AuthReject(RejectInfo),
+    SsidTempDisabled(TempDisabledInfo),
+    WpsSuccess,
+    WpsOverlap,
+    WpsTimeout,
+    WpsFail {
+        config_error: Option<u16>,
+    },
+    /// wpa_supplicant needs a credential to proceed with the connection. Answer it using
+    /// `RequestClient::respond_credential`.
+    CredentialRequest {
+        network_id: usize,
+        field: CredentialField,
+        prompt: String,
+    },
+    /// The connection to wpa_supplicant was established, initially or after reconnecting
     Ready,
+    /// wpa_supplicant terminated or its socket failed. Requests are deferred until it is back and
+    /// `Ready` is broadcast again.
+    DaemonLost,
+    /// wpa_supplicant failed to answer the configured number of consecutive keep-alive pings
+    Unresponsive,
+    /// wpa_supplicant answered a keep-alive ping after being reported as unresponsive
+    Recovered,
+    /// Results of a scan requested by a client. Results of scans which wpa_supplicant starts by
+    /// itself are not fetched.
+    ScanResults(ScanResults),
     Unknown(String),
 }
 
 /// Channel for broadcasting events. Subscribing to this channel is equivalent to
 /// "wpa_ctrl_attach". Can be temporarily silenced using broadcast::Receiver's unsubscribe
-pub type BroadcastReceiver = broadcast::Receiver<Broadcast>;
+pub type BroadcastReceiver = broadcast::Receiver<BroadcastMessage>;
+
+#[derive(Debug, Clone)]
+/// A broadcast along with the level of the wpa_supplicant message it originated from. Broadcasts the
+/// runtime generates itself, such as `Ready`, have no level.
+pub struct BroadcastMessage {
+    pub level: Option<Level>,
+    pub broadcast: Broadcast,
+}
+
+impl From<Broadcast> for BroadcastMessage {
+    fn from(broadcast: Broadcast) -> Self {
+        BroadcastMessage {
+            level: None,
+            broadcast,
+        }
+    }
+}
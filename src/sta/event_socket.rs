@@ -1,46 +1,80 @@
 use super::*;
+use crate::parsing::{event_field, split_level};
 
 pub(crate) struct EventSocket {
-    socket_handle: SocketHandle<1024>,
+    socket_handle: SocketHandle,
+    /// Minimum level of the events to receive
+    event_level: Level,
     /// Sends messages to client
-    sender: mpsc::Sender<Event>,
+    sender: mpsc::Sender<(Option<Level>, Event)>,
+    /// Receives unsolicited messages which arrived on the request socket
+    unsolicited_receiver: mpsc::UnboundedReceiver<String>,
 }
 
 #[derive(Debug)]
 pub(crate) enum Event {
-    ScanComplete,
-    Connected,
-    Disconnected,
+    /// Scan results are available. `id` is only sent for scans requested with `use_id=1`,
+    /// as the runtime does.
+    ScanComplete {
+        id: Option<u32>,
+    },
+    /// wpa_supplicant could not start a scan. With `retry`, it tries again by itself.
+    ScanFailed {
+        ret: i32,
+        retry: bool,
+    },
+    Connected(ConnectionInfo),
+    Disconnected(DisconnectionInfo),
     NetworkNotFound,
-    WrongPsk,
+    AssocReject(RejectInfo),
+    AuthReject(RejectInfo),
+    SsidTempDisabled(TempDisabledInfo),
+    WpsSuccess,
+    WpsOverlap,
+    WpsTimeout,
+    WpsFail {
+        config_error: Option<u16>,
+    },
+    CredentialRequest {
+        network_id: usize,
+        field: CredentialField,
+        prompt: String,
+    },
+    Terminating,
     Unknown(String),
 }
 
-pub(crate) type EventReceiver = mpsc::Receiver<Event>;
+/// Receives events along with their level
+pub(crate) type EventReceiver = mpsc::Receiver<(Option<Level>, Event)>;
 
 impl EventSocket {
     pub(crate) async fn new<P>(
         socket: P,
         request_receiver: &mut mpsc::Receiver<Request>,
+        event_level: Level,
     ) -> Result<(EventReceiver, Vec<Request>, Self)>
     where
         P: AsRef<std::path::Path> + std::fmt::Debug,
     {
-        let (socket_handle, deferred_requests) =
+        let (mut socket_handle, deferred_requests) =
             SocketHandle::open(socket, "wpa_ctrl_async.sock", request_receiver).await?;
         // setup the channel for client requests
         let (sender, receiver) = mpsc::channel(32);
+        let (unsolicited_sender, unsolicited_receiver) = mpsc::unbounded_channel();
+        socket_handle.unsolicited_sender = Some(unsolicited_sender);
         Ok((
             receiver,
             deferred_requests,
             Self {
                 socket_handle,
+                event_level,
                 sender,
+                unsolicited_receiver,
             },
         ))
     }
 
-    async fn send_event(&self, event: Event) -> Result {
+    async fn send_event(&self, event: (Option<Level>, Event)) -> Result {
         self.sender
             .send(event)
             .await
@@ -48,39 +82,132 @@ impl EventSocket {
         Ok(())
     }
 
+    /// Channel for forwarding unsolicited messages which arrive on another socket to the event
+    /// stream
+    pub(crate) fn unsolicited_sender(&self) -> Option<mpsc::UnboundedSender<String>> {
+        self.socket_handle.unsolicited_sender.clone()
+    }
+
+    /// Parses `CTRL-REQ-<field>-<network id>-<prompt>`
+    fn parse_credential_request(data_str: &str) -> Option<Event> {
+        let (_, request) = data_str.split_once("CTRL-REQ-")?;
+        let (field, request) = request.split_once('-')?;
+        let (network_id, prompt) = request.split_once('-')?;
+        Some(Event::CredentialRequest {
+            network_id: network_id.parse().ok()?,
+            field: field.parse().ok()?,
+            prompt: prompt.to_string(),
+        })
+    }
+
+    /// Parses an unsolicited message from wpa_supplicant
+    fn parse_event(msg: &str) -> (Option<Level>, Event) {
+        let (level, data_str) = split_level(msg);
+        (level, Self::parse_known_event(data_str))
+    }
+
+    fn parse_known_event(data_str: &str) -> Event {
+        let (name, args) = data_str.split_once(' ').unwrap_or((data_str, ""));
+        if name == "CTRL-EVENT-SCAN-RESULTS" {
+            Event::ScanComplete {
+                id: event_field(args, "id").and_then(|id| id.parse().ok()),
+            }
+        } else if data_str.contains("CTRL-EVENT-SCAN-FAILED") {
+            Event::ScanFailed {
+                ret: event_field(data_str, "ret")
+                    .and_then(|ret| ret.parse().ok())
+                    .unwrap_or_default(),
+                retry: event_field(data_str, "retry").is_some_and(|retry| retry == "1"),
+            }
+        } else if data_str.contains("CTRL-EVENT-CONNECTED") {
+            Event::Connected(ConnectionInfo::from_event(data_str))
+        } else if data_str.contains("CTRL-EVENT-DISCONNECTED") {
+            Event::Disconnected(DisconnectionInfo::from_event(data_str))
+        } else if data_str.contains("CTRL-EVENT-NETWORK-NOT-FOUND") {
+            Event::NetworkNotFound
+        } else if data_str.contains("CTRL-EVENT-ASSOC-REJECT") {
+            Event::AssocReject(RejectInfo::from_event(data_str))
+        } else if data_str.contains("CTRL-EVENT-AUTH-REJECT") {
+            Event::AuthReject(RejectInfo::from_event(data_str))
+        } else if data_str.contains("CTRL-EVENT-SSID-TEMP-DISABLED") {
+            Event::SsidTempDisabled(TempDisabledInfo::from_event(data_str))
+        } else if data_str.contains("WPS-SUCCESS") {
+            Event::WpsSuccess
+        } else if data_str.contains("WPS-OVERLAP-DETECTED") {
+            Event::WpsOverlap
+        } else if data_str.contains("WPS-TIMEOUT") {
+            Event::WpsTimeout
+        } else if data_str.contains("WPS-FAIL") {
+            let config_error = data_str
+                .split_whitespace()
+                .find_map(|field| field.strip_prefix("config_error="))
+                .and_then(|code| code.parse().ok());
+            Event::WpsFail { config_error }
+        } else if data_str.contains("CTRL-EVENT-TERMINATING") {
+            Event::Terminating
+        } else if let Some(event) = Self::parse_credential_request(data_str) {
+            event
+        } else {
+            Event::Unknown(data_str.into())
+        }
+    }
+
     pub(crate) async fn run(mut self) -> Result {
         info!("wpa_ctrl attempting attach");
-        self.socket_handle.socket.send(b"ATTACH").await?;
         loop {
-            match self
-                .socket_handle
-                .socket
-                .recv(&mut self.socket_handle.buffer)
-                .await
-            {
-                Ok(n) => {
-                    let data_str = std::str::from_utf8(&self.socket_handle.buffer[..n])?.trim_end();
-                    debug!("wpa_ctrl event: {data_str}");
-                    if data_str.ends_with("CTRL-EVENT-SCAN-RESULTS") {
-                        self.send_event(Event::ScanComplete).await?;
-                    } else if data_str.contains("CTRL-EVENT-CONNECTED") {
-                        self.send_event(Event::Connected).await?;
-                    } else if data_str.contains("CTRL-EVENT-DISCONNECTED") {
-                        self.send_event(Event::Disconnected).await?;
-                    } else if data_str.contains("CTRL-EVENT-NETWORK-NOT-FOUND") {
-                        self.send_event(Event::NetworkNotFound).await?;
-                    } else if data_str.contains("CTRL-EVENT-SSID-TEMP-DISABLED")
-                        && data_str.contains("reason=WRONG_KEY")
-                    {
-                        self.send_event(Event::WrongPsk).await?;
-                    } else {
-                        self.send_event(Event::Unknown(data_str.into())).await?;
-                    }
-                }
-                Err(e) => {
-                    return Err(error::Error::UnsolicitedIoError(e));
-                }
+            match self.socket_handle.command(b"ATTACH").await {
+                Ok(()) => break,
+                Err(error::Error::Io(e)) => return Err(error::Error::UnsolicitedIoError(e)),
+                Err(_) => tokio::time::sleep(tokio::time::Duration::from_millis(250)).await,
             }
         }
+        // events up to INFO are needed by the runtime itself, lower ones are filtered before
+        // being broadcast
+        let level = self.event_level.min(Level::Info);
+        if let Err(e) = self
+            .socket_handle
+            .command(format!("LEVEL {}", level.value()).as_bytes())
+            .await
+        {
+            warn!("Error while setting event level: {e}");
+        }
+        loop {
+            let data_str = tokio::select!(
+                resp = self.socket_handle.recv() => match resp {
+                    Ok(data_str) => data_str,
+                    Err(error::Error::Io(e)) => return Err(error::Error::UnsolicitedIoError(e)),
+                    Err(e) => {
+                        warn!("Dropping event: {e}");
+                        continue;
+                    }
+                },
+                // messages received on the request socket
+                Some(data_str) = self.unsolicited_receiver.recv() => data_str,
+            );
+            debug!("wpa_ctrl event: {data_str}");
+            self.send_event(Self::parse_event(&data_str)).await?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_scan_results_events() {
+        let cases = [
+            ("<3>CTRL-EVENT-SCAN-RESULTS ", None),
+            ("<3>CTRL-EVENT-SCAN-RESULTS id=7", Some(7)),
+            ("IFNAME=wlan0 <3>CTRL-EVENT-SCAN-RESULTS id=12", Some(12)),
+        ];
+        for (msg, expected) in cases {
+            let (level, event) = EventSocket::parse_event(msg.trim_end());
+            assert_eq!(level, Some(Level::Info), "parsing {msg:?}");
+            assert!(
+                matches!(event, Event::ScanComplete { id } if id == expected),
+                "parsing {msg:?}: {event:?}"
+            );
+        }
     }
 }
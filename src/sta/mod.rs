@@ -1,6 +1,8 @@
 use super::*;
 
-use tokio::time::Duration;
+use crate::keep_alive::{Health, KeepAlive};
+use std::collections::VecDeque;
+use tokio::time::{Duration, Instant};
 
 mod types;
 pub use types::*;
@@ -16,6 +18,11 @@ use event_socket::*;
 
 const PATH_DEFAULT_SERVER: &str = "/var/run/wpa_supplicant/wlan2";
 
+/// Delay before retrying a scan which wpa_supplicant was too busy to start, doubled after each
+/// attempt up to `SCAN_RETRY_MAX_DELAY`
+const SCAN_RETRY_DELAY: Duration = Duration::from_millis(250);
+const SCAN_RETRY_MAX_DELAY: Duration = Duration::from_secs(4);
+
 /// Instance that runs the Wifi process
 pub struct WifiStation {
     /// Path to the socket
@@ -24,49 +31,114 @@ pub struct WifiStation {
     request_receiver: mpsc::Receiver<Request>,
     #[allow(unused)]
     /// Channel for broadcasting alerts
-    broadcast_sender: broadcast::Sender<Broadcast>,
+    broadcast_sender: broadcast::Sender<BroadcastMessage>,
+    /// Minimum level of the wpa_supplicant events to broadcast
+    event_level: Level,
     /// Channel for sending requests to itself
     self_sender: mpsc::Sender<Request>,
     /// Timeout duration in case no valid select response is received
     select_timeout: Duration,
+    /// Timeout duration in case no valid WPS response is received
+    wps_timeout: Duration,
+    /// Timeout duration in case no scan results are received
+    scan_timeout: Duration,
+    /// Default time to wait for wpa_supplicant to respond to a request
+    request_timeout: Duration,
+    /// Size of the largest response accepted from wpa_supplicant
+    max_response_size: usize,
+    /// How often to ping wpa_supplicant to check that it is responsive. None disables the health monitor.
+    ping_interval: Option<Duration>,
+    /// Consecutive ping failures after which wpa_supplicant is reported as unresponsive
+    ping_failure_threshold: u32,
 }
 
 impl WifiStation {
     pub async fn run(mut self) -> Result {
         info!("Starting Wifi Station process");
-        let (socket_handle, mut deferred_requests) = SocketHandle::open(
+        // Scans are queued and run one at a time, so that each one's results go to its callers
+        let mut scans = ScanQueue::default();
+        let mut select_request = None;
+        let mut wps_request = None;
+        loop {
+            let resp = self
+                .run_connection(&mut scans, &mut select_request, &mut wps_request)
+                .await;
+            match resp {
+                Err(error::Error::DaemonLost) | Err(error::Error::UnsolicitedIoError(_)) => {
+                    warn!("Lost connection to wpa_supplicant, reconnecting");
+                    // the running scan was lost with the daemon, queued ones start once
+                    // reconnected, and pending select and WPS requests time out
+                    if let Some(scan) = scans.active.take() {
+                        scan.fail(|| error::Error::DaemonLost);
+                    }
+                    if let Err(e) = self.broadcast_sender.send(Broadcast::DaemonLost.into()) {
+                        warn!("error broadcasting: {e}");
+                    }
+                }
+                resp => return resp,
+            }
+        }
+    }
+
+    /// Connects to wpa_supplicant and handles events and requests until shutdown or until the
+    /// connection is lost.
+    async fn run_connection(
+        &mut self,
+        scans: &mut ScanQueue,
+        select_request: &mut Option<SelectRequest>,
+        wps_request: &mut Option<WpsRequest>,
+    ) -> Result {
+        let (mut socket_handle, mut deferred_requests) = SocketHandle::open(
             &self.socket_path,
             "mapper_wpa_ctrl_sync.sock",
             &mut self.request_receiver,
         )
         .await?;
+        socket_handle.timeout = self.request_timeout;
+        socket_handle.max_response_size = self.max_response_size;
         // We start up a separate socket for receiving the "unexpected" events that
         // gets forwarded to us via the unsolicited_receiver
-        let (unsolicited_receiver, next_deferred_requests, unsolicited) =
-            EventSocket::new(&self.socket_path, &mut self.request_receiver).await?;
+        let (unsolicited_receiver, next_deferred_requests, unsolicited) = EventSocket::new(
+            &self.socket_path,
+            &mut self.request_receiver,
+            self.event_level,
+        )
+        .await?;
+        socket_handle.unsolicited_sender = unsolicited.unsolicited_sender();
         deferred_requests.extend(next_deferred_requests);
         for request in deferred_requests {
             let _ = self.self_sender.send(request).await;
         }
-        self.broadcast_sender.send(Broadcast::Ready)?;
+        if let Err(e) = self.broadcast_sender.send(Broadcast::Ready.into()) {
+            warn!("error broadcasting: {e}");
+        }
         tokio::select!(
             resp = unsolicited.run() => resp,
-            resp = self.run_internal(unsolicited_receiver, socket_handle) => resp,
+            resp = self.run_internal(
+                unsolicited_receiver,
+                socket_handle,
+                scans,
+                select_request,
+                wps_request,
+            ) => resp,
         )
     }
 
     async fn run_internal(
-        mut self,
+        &mut self,
         mut unsolicited_receiver: EventReceiver,
-        mut socket_handle: SocketHandle<10240>,
+        mut socket_handle: SocketHandle,
+        scans: &mut ScanQueue,
+        select_request: &mut Option<SelectRequest>,
+        wps_request: &mut Option<WpsRequest>,
     ) -> Result {
-        // We will collect scan requests and batch respond to them when results are ready
-        let mut scan_requests = Vec::new();
-        let mut select_request = None;
+        let mut keep_alive = KeepAlive::new(self.ping_interval, self.ping_failure_threshold);
+        self.start_next_scan(&mut socket_handle, scans).await?;
         loop {
             enum EventOrRequest {
-                Event(Option<Event>),
+                Event(Option<(Option<Level>, Event)>),
                 Request(Option<Request>),
+                KeepAlive,
             }
 
             let event_or_request = tokio::select!(
@@ -76,18 +148,20 @@ impl WifiStation {
                 request = self.request_receiver.recv() => {
                     EventOrRequest::Request(request)
                 },
+                _ = keep_alive.tick() => EventOrRequest::KeepAlive,
             );
 
             match event_or_request {
                 EventOrRequest::Event(event) => match event {
-                    Some(unsolicited_msg) => {
+                    Some((level, unsolicited_msg)) => {
                         debug!("Unsolicited event: {unsolicited_msg:?}");
-                        Self::handle_event(
+                        self.handle_event(
                             &mut socket_handle,
+                            level,
                             unsolicited_msg,
-                            &mut scan_requests,
-                            &mut select_request,
-                            &mut self.broadcast_sender,
+                            scans,
+                            select_request,
+                            wps_request,
                         )
                         .await?
                     }
@@ -95,93 +169,304 @@ impl WifiStation {
                 },
                 EventOrRequest::Request(request) => match request {
                     Some(Request::Shutdown) => return Ok(()),
+                    Some(Request::WithTimeout(timeout, request)) => {
+                        let default_timeout =
+                            std::mem::replace(&mut socket_handle.timeout, timeout);
+                        let result = self
+                            .handle_request(
+                                &mut socket_handle,
+                                *request,
+                                scans,
+                                select_request,
+                                wps_request,
+                            )
+                            .await;
+                        socket_handle.timeout = default_timeout;
+                        result?
+                    }
                     Some(request) => {
                         self.handle_request(
                             &mut socket_handle,
                             request,
-                            &mut scan_requests,
-                            &mut select_request,
+                            scans,
+                            select_request,
+                            wps_request,
                         )
                         .await?;
                     }
                     None => return Err(error::Error::WifiStationRequestChannelClosed),
                 },
+                EventOrRequest::KeepAlive => {
+                    Self::handle_keep_alive(
+                        &mut socket_handle,
+                        &mut self.broadcast_sender,
+                        &mut keep_alive,
+                    )
+                    .await?
+                }
+            }
+            // the caller got the I/O error of its request, reconnect for the next ones
+            if socket_handle.connection_lost {
+                return Err(error::Error::DaemonLost);
             }
         }
     }
 
-    async fn handle_event<const N: usize>(
-        socket_handle: &mut SocketHandle<N>,
+    async fn handle_keep_alive(
+        socket_handle: &mut SocketHandle,
+        broadcast_sender: &mut broadcast::Sender<BroadcastMessage>,
+        keep_alive: &mut KeepAlive,
+    ) -> Result {
+        let health = match socket_handle.ping().await {
+            Ok(_) => keep_alive.record(true),
+            // the socket failing means wpa_supplicant is gone, reconnect
+            Err(error::Error::Io(e)) => return Err(error::Error::UnsolicitedIoError(e)),
+            Err(e) => {
+                warn!("wpa_supplicant did not answer keep-alive ping: {e}");
+                keep_alive.record(false)
+            }
+        };
+        match health {
+            Some(Health::Unresponsive) => {
+                broadcast_sender.send(Broadcast::Unresponsive.into())?;
+            }
+            Some(Health::Recovered) => {
+                broadcast_sender.send(Broadcast::Recovered.into())?;
+            }
+            None => (),
+        }
+        Ok(())
+    }
+
+    async fn handle_event(
+        &self,
+        socket_handle: &mut SocketHandle,
+        level: Option<Level>,
         event: Event,
-        scan_requests: &mut Vec<oneshot::Sender<Result<Arc<Vec<ScanResult>>>>>,
+        scans: &mut ScanQueue,
         select_request: &mut Option<SelectRequest>,
-        broadcast_sender: &mut broadcast::Sender<Broadcast>,
+        wps_request: &mut Option<WpsRequest>,
     ) -> Result {
+        // broadcasts carry the level of the event, and are dropped below the minimum level
+        let send_broadcast = |broadcast: Broadcast| -> Result {
+            if level.is_none_or(|level| level >= self.event_level) {
+                self.broadcast_sender
+                    .send(BroadcastMessage { level, broadcast })?;
+            }
+            Ok(())
+        };
         match event {
-            Event::ScanComplete => {
-                let _n = socket_handle.socket.send(b"SCAN_RESULTS").await?;
-                let n = socket_handle.socket.recv(&mut socket_handle.buffer).await?;
-                let data_str = std::str::from_utf8(&socket_handle.buffer[..n])?;
-                let mut scan_results = ScanResult::vec_from_str(data_str)?;
-                scan_results.sort_by(|a, b| a.signal.cmp(&b.signal));
-
-                let results = Arc::new(scan_results);
-                while let Some(scan_request) = scan_requests.pop() {
-                    if scan_request.send(Ok(results.clone())).is_err() {
-                        error!("Scan request response channel closed before response sent");
+            Event::ScanComplete { id } => {
+                // results of scans started by wpa_supplicant itself or requested with another
+                // ID are not fetched
+                if let Some(scan) = scans.active.take_if(|scan| scan.scan_id == id) {
+                    match socket_handle.request(b"SCAN_RESULTS").await {
+                        Ok(data_str) => {
+                            let mut scan_results = ScanResult::vec_from_str(&data_str)?;
+                            scan_results.sort_by_key(|a| a.signal);
+                            let snapshot = ScanSnapshot {
+                                results: Arc::new(scan_results),
+                                timestamp: Instant::now(),
+                            };
+                            // scans of some channels or BSSes only report what they found
+                            if scan.request.is_full() {
+                                scans.last = Some(snapshot.clone());
+                            }
+                            // nobody subscribing to broadcasts is no reason to stop the runtime
+                            let results = Broadcast::ScanResults(snapshot.results.clone());
+                            if let Err(e) = send_broadcast(results) {
+                                warn!("error broadcasting: {e}");
+                            }
+                            scan.send(snapshot);
+                        }
+                        Err(error::Error::Timeout) => {
+                            warn!("Timeout while fetching scan results");
+                            scan.fail(|| error::Error::Timeout);
+                        }
+                        Err(error::Error::ResponseTruncated(size)) => {
+                            warn!(
+                                "Scan results exceeded the maximum response size of {size} bytes"
+                            );
+                            scan.fail(|| error::Error::ResponseTruncated(size));
+                        }
+                        // the scan fails with the connection
+                        Err(error::Error::Io(e)) => {
+                            scans.active = Some(scan);
+                            return Err(error::Error::UnsolicitedIoError(e));
+                        }
+                        Err(e) => {
+                            warn!("Error while fetching scan results: {e}");
+                            let message = e.to_string();
+                            scan.fail(|| error::Error::ScanRejected(message.clone()));
+                        }
                     }
                 }
+                // the scan which kept wpa_supplicant busy may be this one, retry right away
+                if let Some(retry) = scans.retry.take() {
+                    retry.abort();
+                }
+                self.start_next_scan(socket_handle, scans).await?;
             }
-            Event::Connected => {
-                broadcast_sender.send(Broadcast::Connected)?;
+            Event::ScanFailed { ret, retry } => {
+                if retry {
+                    debug!("Scan failed to start (ret={ret}), wpa_supplicant will retry");
+                } else {
+                    if let Some(scan) = scans.active.take() {
+                        warn!("Scan failed to start (ret={ret})");
+                        scan.fail(|| error::Error::ScanFailed(ret));
+                    }
+                    self.start_next_scan(socket_handle, scans).await?;
+                }
+            }
+            Event::Connected(info) => {
+                let network_id = info.network_id;
+                send_broadcast(Broadcast::Connected(info))?;
                 if let Some(sender) = select_request.take() {
                     sender.send(Ok(SelectResult::Success));
                 }
+                // once credentials are received, the new network is only known when connected
+                if wps_request
+                    .as_ref()
+                    .is_some_and(|request| request.succeeded)
+                {
+                    let network_id = match network_id {
+                        Some(network_id) => Some(network_id),
+                        None => match Self::get_status(socket_handle).await {
+                            Ok(status) => status.id,
+                            Err(e) => {
+                                warn!("Error while getting status after WPS success: {e}");
+                                None
+                            }
+                        },
+                    };
+                    match network_id {
+                        Some(network_id) => {
+                            if let Some(sender) = wps_request.take() {
+                                sender.send(Ok(WpsResult::Success(network_id)));
+                            }
+                        }
+                        None => warn!("Connected after WPS success but network id is unknown"),
+                    }
+                }
+            }
+            Event::Disconnected(info) => {
+                send_broadcast(Broadcast::Disconnected(info))?;
+            }
+            Event::AssocReject(info) => {
+                send_broadcast(Broadcast::AssocReject(info))?;
             }
-            Event::Disconnected => {
-                broadcast_sender.send(Broadcast::Disconnected)?;
+            Event::AuthReject(info) => {
+                send_broadcast(Broadcast::AuthReject(info))?;
             }
             Event::NetworkNotFound => {
-                broadcast_sender.send(Broadcast::NetworkNotFound)?;
+                send_broadcast(Broadcast::NetworkNotFound)?;
                 if let Some(sender) = select_request.take() {
                     sender.send(Ok(SelectResult::NotFound));
                 }
             }
-            Event::WrongPsk => {
-                broadcast_sender.send(Broadcast::WrongPsk)?;
-                if let Some(sender) = select_request.take() {
-                    sender.send(Ok(SelectResult::WrongPsk));
+            Event::SsidTempDisabled(info) => {
+                let wrong_key = info.reason.as_deref() == Some("WRONG_KEY");
+                send_broadcast(Broadcast::SsidTempDisabled(info))?;
+                if wrong_key {
+                    send_broadcast(Broadcast::WrongPsk)?;
+                    if let Some(sender) = select_request.take() {
+                        sender.send(Ok(SelectResult::WrongPsk));
+                    }
+                }
+            }
+            Event::WpsSuccess => {
+                send_broadcast(Broadcast::WpsSuccess)?;
+                if let Some(request) = wps_request {
+                    request.succeeded = true;
+                }
+            }
+            Event::WpsOverlap => {
+                send_broadcast(Broadcast::WpsOverlap)?;
+                if let Some(sender) = wps_request.take() {
+                    sender.send(Ok(WpsResult::Overlap));
                 }
             }
+            Event::WpsTimeout => {
+                send_broadcast(Broadcast::WpsTimeout)?;
+                if let Some(sender) = wps_request.take() {
+                    sender.send(Ok(WpsResult::Timeout));
+                }
+            }
+            Event::WpsFail { config_error } => {
+                send_broadcast(Broadcast::WpsFail { config_error })?;
+                if let Some(sender) = wps_request.take() {
+                    sender.send(Ok(WpsResult::Fail { config_error }));
+                }
+            }
+            Event::CredentialRequest {
+                network_id,
+                field,
+                prompt,
+            } => {
+                send_broadcast(Broadcast::CredentialRequest {
+                    network_id,
+                    field,
+                    prompt,
+                })?;
+            }
+            Event::Terminating => return Err(error::Error::DaemonLost),
             Event::Unknown(msg) => {
-                broadcast_sender.send(Broadcast::Unknown(msg))?;
+                send_broadcast(Broadcast::Unknown(msg))?;
             }
         }
         Ok(())
     }
 
-    async fn get_status<const N: usize>(socket_handle: &mut SocketHandle<N>) -> Result<Status> {
-        let _n = socket_handle.socket.send(b"STATUS").await?;
-        let n = socket_handle.socket.recv(&mut socket_handle.buffer).await?;
-        let data_str = std::str::from_utf8(&socket_handle.buffer[..n])?.trim_end();
-        parse_status(data_str)
+    async fn get_status(socket_handle: &mut SocketHandle) -> Result<StationStatus> {
+        let data_str = socket_handle.request(b"STATUS").await?;
+        StationStatus::from_response(&data_str)
     }
 
-    async fn handle_request<const N: usize>(
+    async fn get_bss(socket_handle: &mut SocketHandle, bssid: MacAddr) -> Result<BssInfo> {
+        let data_str = socket_handle
+            .request(format!("BSS {bssid}").as_bytes())
+            .await?;
+        if data_str.is_empty() || data_str == "FAIL" {
+            Err(error::Error::BssNotFound(bssid))
+        } else {
+            BssInfo::from_response(&data_str)
+        }
+    }
+
+    async fn get_all_bss(socket_handle: &mut SocketHandle) -> Result<Vec<BssInfo>> {
+        let mut all_bss = Vec::new();
+        // one entry per request: `BSS RANGE=` silently drops the entries which do not fit in
+        // wpa_supplicant's reply buffer
+        let mut cmd = "BSS FIRST".to_string();
+        loop {
+            let data_str = socket_handle.request(cmd.as_bytes()).await?;
+            if data_str == "FAIL" {
+                return Err(error::Error::UnexpectedWifiApRepsonse(data_str));
+            }
+            // nothing is sent after the last entry
+            if data_str.is_empty() {
+                return Ok(all_bss);
+            }
+            let bss = BssInfo::from_response(&data_str)?;
+            cmd = format!("BSS NEXT-{}", bss.id);
+            all_bss.push(bss);
+        }
+    }
+
+    async fn handle_request(
         &self,
-        socket_handle: &mut SocketHandle<N>,
+        socket_handle: &mut SocketHandle,
         request: Request,
-        scan_requests: &mut Vec<oneshot::Sender<Result<Arc<Vec<ScanResult>>>>>,
+        scans: &mut ScanQueue,
         select_request: &mut Option<SelectRequest>,
+        wps_request: &mut Option<WpsRequest>,
     ) -> Result {
         debug!("Handling request: {request:?}");
         match request {
             Request::Custom(custom, response_channel) => {
-                let _n = socket_handle.socket.send(custom.as_bytes()).await?;
-                let n = socket_handle.socket.recv(&mut socket_handle.buffer).await?;
-                let data_str = std::str::from_utf8(&socket_handle.buffer[..n])?.trim_end();
-                debug!("Custom request response: {data_str}");
-                if response_channel.send(Ok(data_str.into())).is_err() {
+                let response = socket_handle.request(custom.as_bytes()).await;
+                debug!("Custom request response: {response:?}");
+                if response_channel.send(response).is_err() {
                     error!("Custom request response channel closed before response sent");
                 }
             }
@@ -190,22 +475,63 @@ impl WifiStation {
                     sender.send(Ok(SelectResult::Timeout));
                 }
             }
-            Request::Scan(response_channel) => {
-                scan_requests.push(response_channel);
-                if let Err(e) = socket_handle.command(b"SCAN").await {
-                    debug!("Error while requesting SCAN: {e}");
+            Request::WpsTimeout => {
+                if let Some(sender) = wps_request.take() {
+                    sender.send(Ok(WpsResult::Timeout));
+                }
+            }
+            Request::Scan(scan_request, response_channel) => {
+                scans.push(scan_request, response_channel);
+                self.start_next_scan(socket_handle, scans).await?;
+            }
+            Request::CachedScan(max_age, response_channel) => {
+                match scans.last.as_ref().filter(|last| last.age() <= max_age) {
+                    Some(last) => {
+                        if response_channel.send(Ok(last.clone())).is_err() {
+                            error!("Scan request response channel closed before response sent");
+                        }
+                    }
+                    None => {
+                        scans.push(ScanRequest::default(), response_channel);
+                        self.start_next_scan(socket_handle, scans).await?;
+                    }
+                }
+            }
+            // a retry which was aborted may still have been sent
+            Request::ScanRetry(id) if id == scans.retry_id => {
+                scans.retry = None;
+                self.start_next_scan(socket_handle, scans).await?;
+            }
+            Request::ScanRetry(id) => debug!("Ignoring stale scan retry {id}"),
+            Request::ScanTimeout(id) => {
+                if let Some(scan) = scans.take(id) {
+                    warn!("Timeout waiting for scan results");
+                    scan.fail(|| error::Error::ScanTimeout);
                 }
+                self.start_next_scan(socket_handle, scans).await?;
             }
             Request::Networks(response_channel) => {
-                let _n = socket_handle.socket.send(b"LIST_NETWORKS").await?;
-                let n = socket_handle.socket.recv(&mut socket_handle.buffer).await?;
-                let data_str = std::str::from_utf8(&socket_handle.buffer[..n])?.trim_end();
-                let network_list =
-                    NetworkResult::vec_from_str(data_str, &mut socket_handle.socket).await?;
-                if response_channel.send(Ok(network_list)).is_err() {
+                let network_list = async {
+                    let data_str = socket_handle.request(b"LIST_NETWORKS").await?;
+                    NetworkResult::vec_from_str(&data_str, socket_handle).await
+                }
+                .await;
+                if response_channel.send(network_list).is_err() {
                     error!("Scan request response channel closed before response sent");
                 }
             }
+            Request::Bss(bssid, response_channel) => {
+                let bss = Self::get_bss(socket_handle, bssid).await;
+                if response_channel.send(bss).is_err() {
+                    error!("BSS request response channel closed before response sent");
+                }
+            }
+            Request::AllBss(response_channel) => {
+                let bss = Self::get_all_bss(socket_handle).await;
+                if response_channel.send(bss).is_err() {
+                    error!("BSS request response channel closed before response sent");
+                }
+            }
             Request::Status(response_channel) => {
                 let status = Self::get_status(socket_handle).await;
                 if response_channel.send(status).is_err() {
@@ -213,32 +539,64 @@ impl WifiStation {
                 }
             }
             Request::AddNetwork(response_channel) => {
-                let _n = socket_handle.socket.send(b"ADD_NETWORK").await?;
-                let n = socket_handle.socket.recv(&mut socket_handle.buffer).await?;
-                let data_str = std::str::from_utf8(&socket_handle.buffer[..n])?.trim_end();
-                let network_id = usize::from_str(data_str)?;
-                if response_channel.send(Ok(network_id)).is_err() {
-                    error!("Scan request response channel closed before response sent");
-                } else {
+                let network_id = async {
+                    let data_str = socket_handle.request(b"ADD_NETWORK").await?;
+                    Ok(usize::from_str(&data_str)?)
+                }
+                .await;
+                if let Ok(network_id) = &network_id {
                     debug!("wpa_ctrl created network {network_id}");
                 }
+                if response_channel.send(network_id).is_err() {
+                    error!("Scan request response channel closed before response sent");
+                }
             }
             Request::SetNetwork(id, param, response) => {
-                let cmd = format!(
-                    "SET_NETWORK {id} {}",
-                    match param {
-                        SetNetwork::Ssid(ssid) => format!("ssid \"{ssid}\""),
-                        SetNetwork::Bssid(bssid) => format!("bssid \"{bssid}\""),
-                        SetNetwork::Psk(psk) => format!("psk \"{psk}\""),
-                        SetNetwork::KeyMgmt(mgmt) => format!("key_mgmt {}", mgmt),
+                let (name, value) = match &param {
+                    SetNetwork::Ssid(ssid) => ("ssid", quote_network_value(ssid)),
+                    SetNetwork::Bssid(bssid) => ("bssid", format!("\"{bssid}\"")),
+                    SetNetwork::Psk(psk) => ("psk", format!("\"{psk}\"")),
+                    SetNetwork::KeyMgmt(mgmt) => ("key_mgmt", join_values(mgmt)),
+                    SetNetwork::SaePassword(password) => {
+                        ("sae_password", quote_network_value(password))
+                    }
+                    SetNetwork::Ieee80211w(pmf) => ("ieee80211w", pmf.to_string()),
+                    SetNetwork::Eap(methods) => ("eap", join_values(methods)),
+                    SetNetwork::Identity(identity) => ("identity", quote_network_value(identity)),
+                    SetNetwork::AnonymousIdentity(identity) => {
+                        ("anonymous_identity", quote_network_value(identity))
+                    }
+                    SetNetwork::Password(password) => ("password", quote_network_value(password)),
+                    SetNetwork::Phase1(phase1) => ("phase1", quote_network_value(phase1)),
+                    SetNetwork::Phase2(phase2) => ("phase2", quote_network_value(phase2)),
+                    SetNetwork::CaCert(path) => ("ca_cert", quote_network_value(path)),
+                    SetNetwork::ClientCert(path) => ("client_cert", quote_network_value(path)),
+                    SetNetwork::PrivateKey(path) => ("private_key", quote_network_value(path)),
+                    SetNetwork::PrivateKeyPasswd(password) => {
+                        ("private_key_passwd", quote_network_value(password))
+                    }
+                    SetNetwork::DomainSuffixMatch(domain) => {
+                        ("domain_suffix_match", quote_network_value(domain))
+                    }
+                };
+                let cmd = format!("SET_NETWORK {id} {name} {value}");
+                // avoid leaking credentials into the logs
+                match param {
+                    SetNetwork::Psk(_)
+                    | SetNetwork::SaePassword(_)
+                    | SetNetwork::Password(_)
+                    | SetNetwork::PrivateKeyPasswd(_) => {
+                        debug!("wpa_ctrl \"SET_NETWORK {id} {name} <redacted>\"")
                     }
-                );
-                debug!("wpa_ctrl \"{cmd}\"");
+                    _ => debug!("wpa_ctrl \"{cmd}\""),
+                }
                 let bytes = cmd.into_bytes();
-                if let Err(e) = socket_handle.command(&bytes).await {
+                let result = socket_handle.command(&bytes).await;
+                if let Err(e) = &result {
                     warn!("Error while setting network parameter: {e}");
                 }
-                let _ = response.send(Ok(()));
+                // eg: FAIL for a key management which wpa_supplicant was built without
+                let _ = response.send(result);
             }
             Request::SaveConfig(response) => {
                 if let Err(e) = socket_handle.command(b"SAVE_CONFIG").await {
@@ -271,17 +629,18 @@ impl WifiStation {
                             None
                         } else {
                             debug!("wpa_ctrl selected network {id}");
-                            let status = Self::get_status(socket_handle).await?;
-                            if let Some(current_id) = status.get("id") {
-                                if current_id == &id.to_string() {
+                            match Self::get_status(socket_handle).await {
+                                Ok(status) if status.id == Some(id) => {
                                     let _ =
                                         response_sender.send(Ok(SelectResult::AlreadyConnected));
                                     None
-                                } else {
+                                }
+                                Ok(_) => Some(response_sender),
+                                Err(e) => {
+                                    // the connection events will still resolve the selection
+                                    warn!("Error while getting status after select: {e}");
                                     Some(response_sender)
                                 }
-                            } else {
-                                Some(response_sender)
                             }
                         }
                     }
@@ -300,12 +659,262 @@ impl WifiStation {
                     ));
                 }
             }
+            Request::Wps(wps, response_sender) => {
+                if wps_request.is_some() {
+                    warn!("WPS request already pending! Dropping this one.");
+                    let _ = response_sender.send(Ok(WpsResult::PendingWps));
+                    return Ok(());
+                }
+                let started = match wps {
+                    Wps::Pbc(bssid) => {
+                        let cmd = match bssid {
+                            Some(bssid) => format!("WPS_PBC {bssid}"),
+                            None => "WPS_PBC".to_string(),
+                        };
+                        socket_handle.command(cmd.as_bytes()).await
+                    }
+                    Wps::Pin(bssid, pin) => {
+                        let cmd = match bssid {
+                            Some(bssid) => format!("WPS_PIN {bssid} {pin}"),
+                            None => format!("WPS_PIN any {pin}"),
+                        };
+                        // on success, wpa_supplicant echoes the PIN in use
+                        match socket_handle.request(cmd.as_bytes()).await {
+                            Ok(data_str) if data_str.starts_with("FAIL") => {
+                                Err(error::Error::UnexpectedWifiApRepsonse(data_str))
+                            }
+                            Ok(_) => Ok(()),
+                            Err(e) => Err(e),
+                        }
+                    }
+                };
+                match started {
+                    Ok(()) => {
+                        debug!("wpa_ctrl started WPS");
+                        *wps_request = Some(WpsRequest::new(
+                            self.self_sender.clone(),
+                            response_sender,
+                            self.wps_timeout,
+                        ));
+                    }
+                    Err(e) => {
+                        warn!("Error while starting WPS: {e}");
+                        let _ = response_sender.send(Err(e));
+                    }
+                }
+            }
+            Request::WpsCancel(response) => {
+                let result = socket_handle.command(b"WPS_CANCEL").await;
+                if result.is_ok() {
+                    debug!("wpa_ctrl cancelled WPS");
+                    if let Some(sender) = wps_request.take() {
+                        sender.send(Ok(WpsResult::Cancelled));
+                    }
+                }
+                let _ = response.send(result);
+            }
+            Request::CredentialResponse(credential, response) => {
+                let CredentialResponse {
+                    network_id,
+                    field,
+                    value,
+                } = credential;
+                debug!("wpa_ctrl responding to {field} request for network {network_id}");
+                let cmd = format!("CTRL-RSP-{field}-{network_id}:{value}");
+                let result = socket_handle.command(cmd.as_bytes()).await;
+                if let Err(e) = &result {
+                    warn!("Error while responding to {field} request: {e}");
+                }
+                let _ = response.send(result);
+            }
+            Request::Ping(response_channel) => {
+                let latency = socket_handle.ping().await;
+                if response_channel.send(latency).is_err() {
+                    error!("Ping request response channel closed before response sent");
+                }
+            }
+            Request::SetLogLevel(level, response) => {
+                let result = socket_handle
+                    .command(format!("LOG_LEVEL {level}").as_bytes())
+                    .await;
+                if let Err(e) = &result {
+                    warn!("Error while setting log level: {e}");
+                }
+                let _ = response.send(result);
+            }
+            Request::SetSaePwe(pwe, response) => {
+                let result = socket_handle
+                    .command(format!("SET sae_pwe {pwe}").as_bytes())
+                    .await;
+                if let Err(e) = &result {
+                    warn!("Error while setting sae_pwe: {e}");
+                }
+                let _ = response.send(result);
+            }
             Request::Shutdown => (), //shutdown is handled at the scope above
+            Request::WithTimeout(..) => (), //timeouts are applied at the scope above
+        }
+        Ok(())
+    }
+
+    /// Starts the next queued scan, unless one is already running or waiting to be retried.
+    /// Scans which wpa_supplicant rejects fail without waiting for results.
+    async fn start_next_scan(
+        &self,
+        socket_handle: &mut SocketHandle,
+        scans: &mut ScanQueue,
+    ) -> Result {
+        while scans.active.is_none() && scans.retry.is_none() {
+            let Some(mut scan) = scans.queued.pop_front() else {
+                break;
+            };
+            // the timeout covers retries, so it starts with the first attempt
+            if scan.timeout.is_none() {
+                let sender = self.self_sender.clone();
+                let (id, timeout) = (scan.id, self.scan_timeout);
+                scan.timeout = Some(tokio::task::spawn(async move {
+                    tokio::time::sleep(timeout).await;
+                    let _ = sender.send(Request::ScanTimeout(id)).await;
+                }));
+            }
+            let command = scan.request.command();
+            match socket_handle.request(command.as_bytes()).await {
+                // the ID of the scan is sent instead of OK, unless wpa_supplicant predates use_id
+                Ok(response) if response == "OK" || response.parse::<u32>().is_ok() => {
+                    debug!("Started scan: {command} ({response})");
+                    scan.scan_id = response.parse().ok();
+                    scans.active = Some(scan);
+                }
+                // another scan is running, eg: one started by wpa_supplicant itself
+                Ok(response) if response == "FAIL-BUSY" => {
+                    let delay = SCAN_RETRY_DELAY
+                        .saturating_mul(1 << scan.attempts.min(8))
+                        .min(SCAN_RETRY_MAX_DELAY);
+                    scan.attempts += 1;
+                    debug!("wpa_supplicant is busy, retrying {command} in {delay:?}");
+                    scans.queued.push_front(scan);
+                    let sender = self.self_sender.clone();
+                    scans.retry_id += 1;
+                    let retry_id = scans.retry_id;
+                    scans.retry = Some(tokio::task::spawn(async move {
+                        tokio::time::sleep(delay).await;
+                        let _ = sender.send(Request::ScanRetry(retry_id)).await;
+                    }));
+                }
+                Ok(response) => {
+                    warn!("wpa_supplicant rejected {command}: {response}");
+                    scan.fail(|| error::Error::ScanRejected(response.clone()));
+                }
+                Err(error::Error::Timeout) => {
+                    warn!("Timeout while requesting {command}");
+                    scan.fail(|| error::Error::Timeout);
+                }
+                // the socket failing means wpa_supplicant is gone, the scan starts once reconnected
+                Err(error::Error::Io(e)) => {
+                    scans.queued.push_front(scan);
+                    return Err(error::Error::UnsolicitedIoError(e));
+                }
+                Err(e) => {
+                    warn!("Error while requesting {command}: {e}");
+                    let message = e.to_string();
+                    scan.fail(|| error::Error::ScanRejected(message.clone()));
+                }
+            }
         }
         Ok(())
     }
 }
 
+/// A scan and the callers awaiting its results
+struct PendingScan {
+    /// Identifies the scan in the queue, for its timeout
+    id: u64,
+    request: ScanRequest,
+    responses: Vec<oneshot::Sender<Result<ScanSnapshot>>>,
+    /// ID of the scan, sent in its `CTRL-EVENT-SCAN-RESULTS`
+    scan_id: Option<u32>,
+    /// Number of times wpa_supplicant was too busy to start the scan
+    attempts: u32,
+    /// Started with the first attempt to start the scan
+    timeout: Option<tokio::task::JoinHandle<()>>,
+}
+
+impl PendingScan {
+    fn send(self, snapshot: ScanSnapshot) {
+        if let Some(timeout) = &self.timeout {
+            timeout.abort();
+        }
+        if let Some(scan_id) = self.scan_id {
+            debug!("Scan {scan_id} complete");
+        }
+        for response in self.responses {
+            if response.send(Ok(snapshot.clone())).is_err() {
+                error!("Scan request response channel closed before response sent");
+            }
+        }
+    }
+
+    /// Fails the scan for every caller, with an error made for each of them
+    fn fail(self, error: impl Fn() -> error::Error) {
+        if let Some(timeout) = &self.timeout {
+            timeout.abort();
+        }
+        for response in self.responses {
+            let _ = response.send(Err(error()));
+        }
+    }
+}
+
+/// Scans requested by clients, which run one at a time so that the results of a scan go to the
+/// callers which requested it
+#[derive(Default)]
+struct ScanQueue {
+    /// Scan started with wpa_supplicant, awaiting its results
+    active: Option<PendingScan>,
+    queued: VecDeque<PendingScan>,
+    /// Results of the last scan of every channel
+    last: Option<ScanSnapshot>,
+    /// Retries the scan at the front of the queue once the delay after `FAIL-BUSY` has passed
+    retry: Option<tokio::task::JoinHandle<()>>,
+    /// Identifies the latest retry, so that earlier ones which fire anyway are ignored
+    retry_id: u64,
+    next_id: u64,
+}
+
+impl ScanQueue {
+    /// Queues a scan, sharing one which has not started yet if it has the same parameters
+    fn push(&mut self, request: ScanRequest, response: oneshot::Sender<Result<ScanSnapshot>>) {
+        match self.queued.iter_mut().find(|scan| scan.request == request) {
+            Some(scan) => scan.responses.push(response),
+            None => {
+                self.queued.push_back(PendingScan {
+                    id: self.next_id,
+                    request,
+                    responses: vec![response],
+                    scan_id: None,
+                    attempts: 0,
+                    timeout: None,
+                });
+                self.next_id += 1;
+            }
+        }
+    }
+
+    /// Removes the scan with the given ID, whether it is running or waiting to be retried
+    fn take(&mut self, id: u64) -> Option<PendingScan> {
+        if self.active.as_ref().is_some_and(|scan| scan.id == id) {
+            return self.active.take();
+        }
+        let index = self.queued.iter().position(|scan| scan.id == id)?;
+        if index == 0 {
+            if let Some(retry) = self.retry.take() {
+                retry.abort();
+            }
+        }
+        self.queued.remove(index)
+    }
+}
+
 struct SelectRequest {
     response: oneshot::Sender<Result<SelectResult>>,
     timeout: tokio::task::JoinHandle<()>,
@@ -331,3 +940,32 @@ impl SelectRequest {
         let _ = self.response.send(result);
     }
 }
+
+struct WpsRequest {
+    response: oneshot::Sender<Result<WpsResult>>,
+    timeout: tokio::task::JoinHandle<()>,
+    /// Credentials were received, awaiting connection to the new network
+    succeeded: bool,
+}
+
+impl WpsRequest {
+    fn new(
+        sender: mpsc::Sender<Request>,
+        response: oneshot::Sender<Result<WpsResult>>,
+        timeout: Duration,
+    ) -> Self {
+        Self {
+            response,
+            timeout: tokio::task::spawn(async move {
+                tokio::time::sleep(timeout).await;
+                let _ = sender.send(Request::WpsTimeout).await;
+            }),
+            succeeded: false,
+        }
+    }
+
+    fn send(self, result: Result<WpsResult>) {
+        self.timeout.abort();
+        let _ = self.response.send(result);
+    }
+}
@@ -1,4 +1,5 @@
 use super::*;
+use crate::socket_handle::DEFAULT_MAX_RESPONSE_SIZE;
 
 /// A convenient default type for setting up the WiFi Station process.
 pub type WifiSetup = WifiSetupGeneric<32, 32>;
@@ -28,8 +29,15 @@ impl<const C: usize, const B: usize> WifiSetupGeneric<C, B> {
                 socket_path: PATH_DEFAULT_SERVER.into(),
                 request_receiver,
                 broadcast_sender,
+                event_level: Level::Info,
                 self_sender,
                 select_timeout: Duration::from_secs(10),
+                wps_timeout: Duration::from_secs(130),
+                scan_timeout: Duration::from_secs(30),
+                request_timeout: Duration::from_secs(5),
+                max_response_size: DEFAULT_MAX_RESPONSE_SIZE,
+                ping_interval: None,
+                ping_failure_threshold: 3,
             },
             request_client,
             broadcast_receiver,
@@ -40,10 +48,50 @@ impl<const C: usize, const B: usize> WifiSetupGeneric<C, B> {
         self.wifi.socket_path = path.into();
     }
 
+    /// Minimum level of the wpa_supplicant events to broadcast. Defaults to `Level::Info`.
+    pub fn set_event_level(&mut self, level: Level) {
+        self.wifi.event_level = level;
+    }
+
     pub fn set_select_timeout(&mut self, timeout: Duration) {
         self.wifi.select_timeout = timeout;
     }
 
+    /// Time for a scan to complete, including retries while wpa_supplicant is busy with another
+    /// scan, after which it fails with `Error::ScanTimeout`. Defaults to 30 seconds.
+    pub fn set_scan_timeout(&mut self, timeout: Duration) {
+        self.wifi.scan_timeout = timeout;
+    }
+
+    /// WPS enrollment lasts up to two minutes (the walk time), after which wpa_supplicant
+    /// reports a timeout itself. This timeout is a fallback in case no such event is received.
+    pub fn set_wps_timeout(&mut self, timeout: Duration) {
+        self.wifi.wps_timeout = timeout;
+    }
+
+    /// How long to wait for wpa_supplicant to respond to a request before it fails with
+    /// `Error::Timeout`. Individual clients may override it with `RequestClient::with_timeout`.
+    pub fn set_request_timeout(&mut self, timeout: Duration) {
+        self.wifi.request_timeout = timeout;
+    }
+
+    /// Responses from wpa_supplicant larger than this fail with `Error::ResponseTruncated` instead of
+    /// being parsed. Defaults to 64 KiB.
+    pub fn set_max_response_size(&mut self, size: usize) {
+        self.wifi.max_response_size = size;
+    }
+
+    /// Enables the health monitor, which pings wpa_supplicant at this interval and broadcasts
+    /// `Broadcast::Unresponsive` once it fails to answer too many consecutive pings.
+    pub fn set_ping_interval(&mut self, interval: Duration) {
+        self.wifi.ping_interval = Some(interval);
+    }
+
+    /// Consecutive ping failures after which wpa_supplicant is reported as unresponsive. Defaults to 3.
+    pub fn set_ping_failure_threshold(&mut self, threshold: u32) {
+        self.wifi.ping_failure_threshold = threshold;
+    }
+
     pub fn get_broadcast_receiver(&self) -> BroadcastReceiver {
         self.wifi.broadcast_sender.subscribe()
     }
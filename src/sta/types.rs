@@ -1,17 +1,24 @@
-use super::{error, warn, Result};
+use super::{error, warn, MacAddr, Result};
+use crate::ie::{self, AkmSuite, CipherSuite, InformationElement};
+use crate::ieee80211::ReasonCode;
+use crate::parsing::{event_field, take_hex, take_parsed};
+use crate::socket_handle::SocketHandle;
+use crate::{ChannelWidth, Frequency};
 use serde::Serialize;
 use std::collections::HashMap;
 use std::fmt::Display;
+use std::net::IpAddr;
 use std::str::FromStr;
-use tokio::net::UnixDatagram;
 
 #[derive(Serialize, Debug, Clone)]
 /// The result from scanning for networks.
 pub struct ScanResult {
     pub mac: String,
-    pub frequency: String,
+    pub frequency: Frequency,
     pub signal: isize,
     pub flags: String,
+    /// The security of the network, parsed from `flags`
+    pub security: SecurityFlags,
     pub name: String,
 }
 
@@ -40,12 +47,17 @@ impl ScanResult {
                     }
                 }
                 if let Some(name) = name {
+                    let Ok(frequency) = Frequency::from_str(frequency) else {
+                        warn!("Invalid string for frequency: {frequency}");
+                        continue;
+                    };
                     if let Ok(signal) = isize::from_str(signal) {
                         let scan_result = ScanResult {
                             mac: mac.to_string(),
-                            frequency: frequency.to_string(),
+                            frequency,
                             signal,
                             flags: flags.to_string(),
+                            security: SecurityFlags::from_flags(flags),
                             name,
                         };
                         results.push(scan_result);
@@ -59,6 +71,255 @@ impl ScanResult {
     }
 }
 
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// Parameters of a scan, eg: to look for a hidden network on the channels it was last seen on
+/// when reconnecting. The default scans every channel for any network.
+pub struct ScanRequest {
+    frequencies: Vec<Frequency>,
+    ssids: Vec<Vec<u8>>,
+    bssid: Option<MacAddr>,
+    passive: bool,
+    only_new: bool,
+    scan_only: bool,
+}
+
+impl ScanRequest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only scan the given frequency. May be called more than once to scan several.
+    pub fn frequency(mut self, frequency: Frequency) -> Self {
+        self.frequencies.push(frequency);
+        self
+    }
+
+    pub fn frequencies<I: IntoIterator<Item = Frequency>>(mut self, frequencies: I) -> Self {
+        self.frequencies.extend(frequencies);
+        self
+    }
+
+    /// Probe for the given SSID, which finds hidden networks. May be called more than once to
+    /// probe for several.
+    pub fn ssid<S: AsRef<[u8]>>(mut self, ssid: S) -> Self {
+        self.ssids.push(ssid.as_ref().to_vec());
+        self
+    }
+
+    /// Only report the BSS with the given BSSID
+    pub fn bssid(mut self, bssid: MacAddr) -> Self {
+        self.bssid = Some(bssid);
+        self
+    }
+
+    /// Listen for beacons instead of sending probe requests
+    pub fn passive(mut self) -> Self {
+        self.passive = true;
+        self
+    }
+
+    /// Only report the BSSes seen by this scan, not the ones remembered from earlier scans
+    pub fn only_new(mut self) -> Self {
+        self.only_new = true;
+        self
+    }
+
+    /// Only scan, without wpa_supplicant connecting to a network based on the results
+    pub fn scan_only(mut self) -> Self {
+        self.scan_only = true;
+        self
+    }
+
+    /// Whether the scan covers every channel and reports every BSS found
+    pub(crate) fn is_full(&self) -> bool {
+        self.frequencies.is_empty() && self.bssid.is_none() && !self.only_new
+    }
+
+    /// The `SCAN` command with its parameters. `TYPE=ONLY` is only recognised as the first one.
+    pub(crate) fn command(&self) -> String {
+        let mut command = String::from("SCAN");
+        if self.scan_only {
+            command.push_str(" TYPE=ONLY");
+        }
+        if !self.frequencies.is_empty() {
+            let frequencies: Vec<String> = self
+                .frequencies
+                .iter()
+                .map(|frequency| frequency.mhz().to_string())
+                .collect();
+            command.push_str(&format!(" freq={}", frequencies.join(",")));
+        }
+        for ssid in &self.ssids {
+            let hex: String = ssid.iter().map(|b| format!("{b:02x}")).collect();
+            command.push_str(&format!(" ssid {hex}"));
+        }
+        if let Some(bssid) = self.bssid {
+            command.push_str(&format!(" bssid={bssid}"));
+        }
+        if self.passive {
+            command.push_str(" passive=1");
+        }
+        if self.only_new {
+            command.push_str(" only_new=1");
+        }
+        // the ID tells the results of this scan from those of scans wpa_supplicant starts itself
+        command.push_str(" use_id=1");
+        command
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+/// The flags of a scan result, eg: `[WPA2-PSK+SAE-CCMP][WPS][ESS]`
+pub struct SecurityFlags {
+    /// One entry per security element advertised, eg: WPA and RSN for WPA/WPA2 mixed mode
+    pub protocols: Vec<SecurityProtocol>,
+    pub wep: bool,
+    /// Wi-Fi Protected Setup is supported, including the `WPS-PBC`, `WPS-PIN` and `WPS-AUTH`
+    /// variants
+    pub wps: bool,
+    pub ess: bool,
+    pub ibss: bool,
+    pub mesh: bool,
+    pub p2p: bool,
+    /// Hotspot 2.0 (Passpoint)
+    pub hs20: bool,
+    /// SAE hash-to-element is supported
+    pub sae_h2e: bool,
+    /// The network is one half of an OWE transition mode pair
+    pub owe_transition: bool,
+    /// Flags which are not recognised, without their brackets
+    pub other: Vec<String>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+/// A security element of a scan result's flags, eg: `WPA2-PSK+SAE-CCMP-preauth`
+pub struct SecurityProtocol {
+    pub protocol: Protocol,
+    pub key_mgmt: Vec<AkmSuite>,
+    pub pairwise_ciphers: Vec<CipherSuite>,
+    pub preauth: bool,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    /// The original WPA element
+    Wpa,
+    /// The RSN element, used by WPA2 and WPA3. wpa_supplicant names it `RSN` for mesh BSSes and
+    /// `WPA2` otherwise.
+    Rsn,
+    /// OSU Server-only authenticated layer 2 Encryption Network, used by Hotspot 2.0
+    Osen,
+}
+
+impl SecurityFlags {
+    pub fn from_flags(flags: &str) -> SecurityFlags {
+        let mut security = SecurityFlags::default();
+        for flag in flags.split(['[', ']']).filter(|flag| !flag.is_empty()) {
+            match flag {
+                "WEP" => security.wep = true,
+                "ESS" => security.ess = true,
+                "IBSS" => security.ibss = true,
+                "MESH" => security.mesh = true,
+                "P2P" => security.p2p = true,
+                "HS20" => security.hs20 = true,
+                "SAE-H2E" => security.sae_h2e = true,
+                "OWE-TRANS" | "OWE-TRANS-OPEN" => security.owe_transition = true,
+                flag if flag == "WPS" || flag.starts_with("WPS-") => security.wps = true,
+                flag => match SecurityProtocol::from_flag(flag) {
+                    Some(protocol) => security.protocols.push(protocol),
+                    None => security.other.push(flag.to_string()),
+                },
+            }
+        }
+        security
+    }
+
+    /// Whether the network requires no authentication, which includes the open half of OWE
+    /// transition networks. A security element which could not be parsed counts as protected.
+    pub fn is_open(&self) -> bool {
+        let unparsed_protocol = self.other.iter().any(|flag| {
+            ["WPA", "RSN", "OSEN"]
+                .iter()
+                .any(|protocol| flag.starts_with(protocol))
+        });
+        self.protocols.is_empty() && !self.wep && !unparsed_protocol
+    }
+
+    /// The key management to configure on a network to join this one, eg: `WpaPsk` and `Sae`
+    /// for a WPA2/WPA3-Personal transition network, or `None` for an open or WEP network. Key
+    /// management suites which `KeyMgmt` does not represent, such as FILS, are left out, so the
+    /// result is empty if the network only supports those.
+    pub fn required_key_mgmt(&self) -> Vec<KeyMgmt> {
+        if self.is_open() {
+            return vec![KeyMgmt::None];
+        }
+        let mut key_mgmt = Vec::new();
+        let suites = self
+            .protocols
+            .iter()
+            .flat_map(|protocol| protocol.key_mgmt.iter());
+        for suite in suites {
+            let mgmt = match suite {
+                AkmSuite::Ieee8021x => KeyMgmt::WpaEap,
+                AkmSuite::Psk => KeyMgmt::WpaPsk,
+                AkmSuite::FtIeee8021x => KeyMgmt::FtEap,
+                AkmSuite::FtPsk => KeyMgmt::FtPsk,
+                AkmSuite::Ieee8021xSha256 => KeyMgmt::WpaEapSha256,
+                AkmSuite::PskSha256 => KeyMgmt::WpaPskSha256,
+                AkmSuite::Sae => KeyMgmt::Sae,
+                AkmSuite::FtSae => KeyMgmt::FtSae,
+                AkmSuite::SuiteB => KeyMgmt::WpaEapSuiteB,
+                AkmSuite::SuiteB192 => KeyMgmt::WpaEapSuiteB192,
+                AkmSuite::FtIeee8021xSha384 => KeyMgmt::FtEapSha384,
+                AkmSuite::Owe => KeyMgmt::Owe,
+                AkmSuite::Dpp => KeyMgmt::Dpp,
+                _ => continue,
+            };
+            if !key_mgmt.contains(&mgmt) {
+                key_mgmt.push(mgmt);
+            }
+        }
+        if self.wep && key_mgmt.is_empty() {
+            key_mgmt.push(KeyMgmt::None);
+        }
+        key_mgmt
+    }
+}
+
+impl SecurityProtocol {
+    /// Parses a `<protocol>-<key mgmt>-<ciphers>[-preauth]` flag. Since the names of key
+    /// management and cipher suites contain dashes themselves, the split between them is the one
+    /// for which every name is known.
+    fn from_flag(flag: &str) -> Option<SecurityProtocol> {
+        let (protocol, rest) = flag.split_once('-')?;
+        let protocol = match protocol {
+            "WPA" => Protocol::Wpa,
+            "WPA2" | "RSN" => Protocol::Rsn,
+            "OSEN" => Protocol::Osen,
+            _ => return None,
+        };
+        let (rest, preauth) = match rest.strip_suffix("-preauth") {
+            Some(rest) => (rest, true),
+            None => (rest, false),
+        };
+        rest.match_indices('-').find_map(|(index, _)| {
+            let (key_mgmt, ciphers) = (&rest[..index], &rest[index + 1..]);
+            Some(SecurityProtocol {
+                protocol,
+                key_mgmt: key_mgmt
+                    .split('+')
+                    .map(AkmSuite::from_name)
+                    .collect::<Option<_>>()?,
+                pairwise_ciphers: ciphers
+                    .split('+')
+                    .map(CipherSuite::from_name)
+                    .collect::<Option<_>>()?,
+                preauth,
+            })
+        })
+    }
+}
+
 #[derive(Serialize, Debug, Clone)]
 /// A known WiFi network.
 pub struct NetworkResult {
@@ -68,21 +329,18 @@ pub struct NetworkResult {
 }
 
 impl NetworkResult {
-    pub async fn vec_from_str(
+    pub(crate) async fn vec_from_str(
         response: &str,
-        socket: &mut UnixDatagram,
+        socket_handle: &mut SocketHandle,
     ) -> Result<Vec<NetworkResult>> {
-        let mut buffer = [0; 256];
         let mut results = Vec::new();
         let split = response.split('\n').skip(1);
         for line in split {
             let mut line_split = line.split_whitespace();
             if let Some(network_id) = line_split.next() {
                 let cmd = format!("GET_NETWORK {network_id} ssid");
-                let bytes = cmd.into_bytes();
-                socket.send(&bytes).await?;
-                let n = socket.recv(&mut buffer).await?;
-                let ssid = std::str::from_utf8(&buffer[..n])?.trim_matches('\"');
+                let response = socket_handle.request(cmd.as_bytes()).await?;
+                let ssid = response.trim_matches('\"');
                 if let Ok(network_id) = usize::from_str(network_id) {
                     if let Some(flags) = line_split.last() {
                         results.push(NetworkResult {
@@ -100,40 +358,901 @@ impl NetworkResult {
     }
 }
 
-/// A HashMap of what is returned when running `wpa_cli status`.
-pub type Status = HashMap<String, String>;
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Details of `CTRL-EVENT-CONNECTED - Connection to <bssid> completed [id=<id> id_str=<id_str>]`
+pub struct ConnectionInfo {
+    pub bssid: Option<MacAddr>,
+    pub network_id: Option<usize>,
+    pub id_str: Option<String>,
+}
+
+impl ConnectionInfo {
+    pub(crate) fn from_event(event: &str) -> ConnectionInfo {
+        let bssid = event
+            .split_once("Connection to ")
+            .and_then(|(_, rest)| rest.split_whitespace().next())
+            .and_then(|bssid| MacAddr::from_str(bssid).ok());
+        let details = event
+            .split_once('[')
+            .map(|(_, details)| details.trim_end_matches(']'))
+            .unwrap_or_default();
+        ConnectionInfo {
+            bssid,
+            network_id: event_field(details, "id").and_then(|id| id.parse().ok()),
+            id_str: event_field(details, "id_str").filter(|id_str| !id_str.is_empty()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Details of `CTRL-EVENT-DISCONNECTED bssid=<bssid> reason=<code> [locally_generated=1]`
+pub struct DisconnectionInfo {
+    pub bssid: Option<MacAddr>,
+    pub reason: Option<ReasonCode>,
+    /// Whether the disconnection was initiated by this station rather than the AP
+    pub locally_generated: bool,
+}
+
+impl DisconnectionInfo {
+    pub(crate) fn from_event(event: &str) -> DisconnectionInfo {
+        DisconnectionInfo {
+            bssid: event_field(event, "bssid").and_then(|bssid| MacAddr::from_str(&bssid).ok()),
+            reason: event_field(event, "reason")
+                .and_then(|reason| reason.parse::<u16>().ok())
+                .map(ReasonCode::from),
+            locally_generated: event_field(event, "locally_generated").as_deref() == Some("1"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Details of `CTRL-EVENT-ASSOC-REJECT` or `CTRL-EVENT-AUTH-REJECT`, with the 802.11 status code
+/// sent by the AP.
+pub struct RejectInfo {
+    pub bssid: Option<MacAddr>,
+    pub status_code: Option<u16>,
+}
+
+impl RejectInfo {
+    pub(crate) fn from_event(event: &str) -> RejectInfo {
+        // auth rejects report the BSSID as the first field, assoc rejects as `bssid=`
+        let bssid = event_field(event, "bssid").or_else(|| {
+            event
+                .split_once("-REJECT ")
+                .and_then(|(_, rest)| rest.split_whitespace().next())
+                .map(str::to_string)
+        });
+        RejectInfo {
+            bssid: bssid.and_then(|bssid| MacAddr::from_str(&bssid).ok()),
+            status_code: event_field(event, "status_code").and_then(|code| code.parse().ok()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Details of `CTRL-EVENT-SSID-TEMP-DISABLED`, emitted when wpa_supplicant stops trying a network
+/// for a while after repeated failures.
+pub struct TempDisabledInfo {
+    pub network_id: Option<usize>,
+    pub ssid: Option<String>,
+    pub auth_failures: Option<u32>,
+    /// Seconds during which the network is disabled
+    pub duration: Option<u32>,
+    /// Reason for disabling the network, eg: `WRONG_KEY` or `CONN_FAILED`
+    pub reason: Option<String>,
+}
 
-pub(crate) fn parse_status(response: &str) -> Result<Status> {
-    use config::{Config, File, FileFormat};
-    let config = Config::builder()
-        .add_source(File::from_str(response, FileFormat::Ini))
-        .build()
-        .map_err(|e| error::Error::ParsingWifiStatus {
+impl TempDisabledInfo {
+    pub(crate) fn from_event(event: &str) -> TempDisabledInfo {
+        TempDisabledInfo {
+            network_id: event_field(event, "id").and_then(|id| id.parse().ok()),
+            ssid: event_field(event, "ssid"),
+            auth_failures: event_field(event, "auth_failures").and_then(|n| n.parse().ok()),
+            duration: event_field(event, "duration").and_then(|n| n.parse().ok()),
+            reason: event_field(event, "reason"),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+/// What is returned when running `wpa_cli status`. Fields which are not parsed into a typed
+/// field are kept in `extra`.
+pub struct StationStatus {
+    pub wpa_state: WpaState,
+    pub bssid: Option<MacAddr>,
+    pub freq: Option<Frequency>,
+    pub ssid: Option<String>,
+    pub id: Option<usize>,
+    pub id_str: Option<String>,
+    pub mode: Option<String>,
+    pub pairwise_cipher: Option<String>,
+    pub group_cipher: Option<String>,
+    pub key_mgmt: Option<String>,
+    pub ip_address: Option<IpAddr>,
+    pub address: Option<MacAddr>,
+    pub extra: HashMap<String, String>,
+}
+
+impl StationStatus {
+    pub fn from_response(response: &str) -> Result<StationStatus> {
+        use config::{Config, File, FileFormat};
+        let map_err = |e| error::Error::ParsingWifiStatus {
             e,
             s: response.into(),
-        })?;
-    Ok(config.try_deserialize::<HashMap<String, String>>().unwrap())
+        };
+        let mut fields = Config::builder()
+            .add_source(File::from_str(response, FileFormat::Ini))
+            .build()
+            .and_then(|config| config.try_deserialize::<HashMap<String, String>>())
+            .map_err(map_err)?;
+
+        Ok(StationStatus {
+            wpa_state: take_parsed(&mut fields, "wpa_state")
+                .unwrap_or(WpaState::Unknown(String::new())),
+            bssid: take_parsed(&mut fields, "bssid"),
+            freq: take_parsed(&mut fields, "freq"),
+            ssid: fields.remove("ssid"),
+            id: take_parsed(&mut fields, "id"),
+            id_str: fields.remove("id_str"),
+            mode: fields.remove("mode"),
+            pairwise_cipher: fields.remove("pairwise_cipher"),
+            group_cipher: fields.remove("group_cipher"),
+            key_mgmt: fields.remove("key_mgmt"),
+            ip_address: take_parsed(&mut fields, "ip_address"),
+            address: take_parsed(&mut fields, "address"),
+            extra: fields,
+        })
+    }
 }
 
-#[derive(Debug)]
-/// Key management types for WiFi networks (eg: WPA-PSK, WPA-EAP, etc). In theory, more than one may
-/// be configured, but I believe `wpa_supplicant` defaults to all of them if omitted. Therefore, in
-/// practice, this is mostly important for setting `key_mgmt` to `None` for an open network.
+#[derive(Serialize, Debug, Clone)]
+/// Details about a BSS seen while scanning, as returned by `wpa_cli bss`. Fields which are not
+/// parsed into a typed field are kept in `extra`.
+pub struct BssInfo {
+    /// Identifier of the BSS in wpa_supplicant's BSS table
+    pub id: usize,
+    pub bssid: MacAddr,
+    pub freq: Option<Frequency>,
+    /// Beacon interval in time units (1.024 ms)
+    pub beacon_interval: Option<u16>,
+    /// IEEE 802.11 capability information field
+    pub capabilities: Option<u16>,
+    pub qual: Option<i32>,
+    /// Noise level in dBm
+    pub noise: Option<i32>,
+    /// Signal level in dBm
+    pub level: Option<i32>,
+    /// Signal to noise ratio in dB
+    pub snr: Option<i32>,
+    /// Timestamp of the last beacon or probe response, as sent by the AP
+    pub tsf: Option<u64>,
+    /// Seconds since the BSS was last seen
+    pub age: Option<u32>,
+    /// Estimated throughput in kbps
+    pub est_throughput: Option<u32>,
+    pub flags: Option<String>,
+    pub ssid: Option<String>,
+    /// Information elements from the last probe response or beacon
+    pub ie: Vec<u8>,
+    /// Information elements from the last beacon
+    pub beacon_ie: Vec<u8>,
+    /// ANQP and Hotspot 2.0 elements fetched from the BSS, keyed by name (eg:
+    /// `anqp_venue_name`), with hex encoded values
+    pub anqp: HashMap<String, String>,
+    pub extra: HashMap<String, String>,
+}
+
+impl BssInfo {
+    pub fn from_response(response: &str) -> Result<BssInfo> {
+        let mut fields: HashMap<String, String> = response
+            .lines()
+            .filter_map(|line| line.split_once('='))
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        let id = fields
+            .remove("id")
+            .and_then(|id| id.parse().ok())
+            .ok_or_else(|| error::Error::UnexpectedWifiApRepsonse(response.into()))?;
+        let bssid = MacAddr::from_str(&fields.remove("bssid").unwrap_or_default())?;
+        let capabilities = fields
+            .get("capabilities")
+            .and_then(|caps| u16::from_str_radix(caps.trim_start_matches("0x"), 16).ok());
+        if capabilities.is_some() {
+            fields.remove("capabilities");
+        }
+        let anqp_keys: Vec<String> = fields
+            .keys()
+            .filter(|key| key.starts_with("anqp_") || key.starts_with("hs20_"))
+            .cloned()
+            .collect();
+        let anqp = anqp_keys
+            .into_iter()
+            .filter_map(|key| fields.remove_entry(&key))
+            .collect();
+        Ok(BssInfo {
+            id,
+            bssid,
+            freq: take_parsed(&mut fields, "freq"),
+            beacon_interval: take_parsed(&mut fields, "beacon_int"),
+            capabilities,
+            qual: take_parsed(&mut fields, "qual"),
+            noise: take_parsed(&mut fields, "noise"),
+            level: take_parsed(&mut fields, "level"),
+            snr: take_parsed(&mut fields, "snr"),
+            tsf: take_parsed(&mut fields, "tsf"),
+            age: take_parsed(&mut fields, "age"),
+            est_throughput: take_parsed(&mut fields, "est_throughput"),
+            flags: fields.remove("flags"),
+            ssid: fields.remove("ssid"),
+            ie: take_hex(&mut fields, "ie"),
+            beacon_ie: take_hex(&mut fields, "beacon_ie"),
+            anqp,
+            extra: fields,
+        })
+    }
+
+    /// Decodes the information elements from the last probe response or beacon
+    pub fn information_elements(&self) -> Vec<InformationElement> {
+        ie::parse(&self.ie)
+    }
+
+    /// Width of the channel, from the HE, VHT and HT operation elements
+    pub fn channel_width(&self) -> ChannelWidth {
+        let mut width = ChannelWidth::Mhz20;
+        for element in self.information_elements() {
+            match element {
+                InformationElement::HeOperation(he) => {
+                    if let Some(six_ghz) = he.six_ghz_operation {
+                        // 6 GHz uses the center frequency segments like VHT does for 160 MHz
+                        return match six_ghz.control & 0x03 {
+                            0 => ChannelWidth::Mhz20,
+                            1 => ChannelWidth::Mhz40,
+                            2 => ChannelWidth::Mhz80,
+                            _ if six_ghz.center_freq_seg0.abs_diff(six_ghz.center_freq_seg1)
+                                == 8 =>
+                            {
+                                ChannelWidth::Mhz160
+                            }
+                            _ => ChannelWidth::Mhz80Plus80,
+                        };
+                    }
+                }
+                InformationElement::VhtOperation(vht) => match vht.channel_width {
+                    1 => {
+                        return match vht.center_freq_seg1 {
+                            0 => ChannelWidth::Mhz80,
+                            seg1 if vht.center_freq_seg0.abs_diff(seg1) == 8 => {
+                                ChannelWidth::Mhz160
+                            }
+                            _ => ChannelWidth::Mhz80Plus80,
+                        };
+                    }
+                    // deprecated signalling, with seg0 as the center of the whole 160 MHz
+                    // channel, or of the primary 80 MHz for 80+80 MHz
+                    2 => return ChannelWidth::Mhz160,
+                    3 => return ChannelWidth::Mhz80Plus80,
+                    _ => (),
+                },
+                InformationElement::HtOperation(ht)
+                    if ht.any_channel_width() && ht.secondary_channel_offset() != 0 =>
+                {
+                    width = ChannelWidth::Mhz40;
+                }
+                _ => (),
+            }
+        }
+        width
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// State of the wpa_supplicant connection state machine (`wpa_state`).
+pub enum WpaState {
+    Disconnected,
+    InterfaceDisabled,
+    Inactive,
+    Scanning,
+    Authenticating,
+    Associating,
+    Associated,
+    FourWayHandshake,
+    GroupHandshake,
+    Completed,
+    Unknown(String),
+}
+
+impl FromStr for WpaState {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Ok(match s {
+            "DISCONNECTED" => WpaState::Disconnected,
+            "INTERFACE_DISABLED" => WpaState::InterfaceDisabled,
+            "INACTIVE" => WpaState::Inactive,
+            "SCANNING" => WpaState::Scanning,
+            "AUTHENTICATING" => WpaState::Authenticating,
+            "ASSOCIATING" => WpaState::Associating,
+            "ASSOCIATED" => WpaState::Associated,
+            "4WAY_HANDSHAKE" => WpaState::FourWayHandshake,
+            "GROUP_HANDSHAKE" => WpaState::GroupHandshake,
+            "COMPLETED" => WpaState::Completed,
+            other => WpaState::Unknown(other.to_string()),
+        })
+    }
+}
+
+impl Display for WpaState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            WpaState::Disconnected => "DISCONNECTED",
+            WpaState::InterfaceDisabled => "INTERFACE_DISABLED",
+            WpaState::Inactive => "INACTIVE",
+            WpaState::Scanning => "SCANNING",
+            WpaState::Authenticating => "AUTHENTICATING",
+            WpaState::Associating => "ASSOCIATING",
+            WpaState::Associated => "ASSOCIATED",
+            WpaState::FourWayHandshake => "4WAY_HANDSHAKE",
+            WpaState::GroupHandshake => "GROUP_HANDSHAKE",
+            WpaState::Completed => "COMPLETED",
+            WpaState::Unknown(other) => other,
+        };
+        write!(f, "{}", str)
+    }
+}
+
+impl Serialize for WpaState {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Key management types for WiFi networks (eg: WPA-PSK, WPA-EAP, SAE, etc). More than one may be
+/// configured on a network, which is how transition modes (eg: WPA2/WPA3-Personal with
+/// `WpaPsk` and `Sae`) are set up. If omitted, `wpa_supplicant` defaults to `WPA-PSK WPA-EAP`.
 pub enum KeyMgmt {
     None,
     WpaPsk,
     WpaEap,
     IEEE8021X,
+    FtPsk,
+    FtEap,
+    FtEapSha384,
+    WpaPskSha256,
+    WpaEapSha256,
+    Sae,
+    FtSae,
+    Owe,
+    WpaEapSuiteB,
+    WpaEapSuiteB192,
+    Dpp,
 }
 
 impl Display for KeyMgmt {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let str = match self {
-            KeyMgmt::None => "NONE".to_string(),
-            KeyMgmt::WpaPsk => "WPA-PSK".to_string(),
-            KeyMgmt::WpaEap => "WPA-EAP".to_string(),
-            KeyMgmt::IEEE8021X => "IEEE8021X".to_string(),
+            KeyMgmt::None => "NONE",
+            KeyMgmt::WpaPsk => "WPA-PSK",
+            KeyMgmt::WpaEap => "WPA-EAP",
+            KeyMgmt::IEEE8021X => "IEEE8021X",
+            KeyMgmt::FtPsk => "FT-PSK",
+            KeyMgmt::FtEap => "FT-EAP",
+            KeyMgmt::FtEapSha384 => "FT-EAP-SHA384",
+            KeyMgmt::WpaPskSha256 => "WPA-PSK-SHA256",
+            KeyMgmt::WpaEapSha256 => "WPA-EAP-SHA256",
+            KeyMgmt::Sae => "SAE",
+            KeyMgmt::FtSae => "FT-SAE",
+            KeyMgmt::Owe => "OWE",
+            KeyMgmt::WpaEapSuiteB => "WPA-EAP-SUITE-B",
+            KeyMgmt::WpaEapSuiteB192 => "WPA-EAP-SUITE-B-192",
+            KeyMgmt::Dpp => "DPP",
+        };
+        write!(f, "{}", str)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Protected Management Frames (802.11w) policy for a network (`ieee80211w`). WPA3 networks
+/// require at least `Optional`, and `Required` when SAE is the only key management.
+pub enum Pmf {
+    Disabled,
+    Optional,
+    Required,
+}
+
+impl Display for Pmf {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = match self {
+            Pmf::Disabled => 0,
+            Pmf::Optional => 1,
+            Pmf::Required => 2,
+        };
+        write!(f, "{}", value)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Mechanism used to derive the SAE password element (`sae_pwe`).
+pub enum SaePwe {
+    HuntingAndPecking,
+    HashToElement,
+    Both,
+}
+
+impl Display for SaePwe {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = match self {
+            SaePwe::HuntingAndPecking => 0,
+            SaePwe::HashToElement => 1,
+            SaePwe::Both => 2,
+        };
+        write!(f, "{}", value)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// EAP methods for WPA-Enterprise networks (`eap` network parameter).
+pub enum EapMethod {
+    Peap,
+    Ttls,
+    Tls,
+    Pwd,
+    Fast,
+    Leap,
+    Md5,
+    MschapV2,
+    Gtc,
+    Otp,
+    Sim,
+    Aka,
+    AkaPrime,
+}
+
+impl Display for EapMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            EapMethod::Peap => "PEAP",
+            EapMethod::Ttls => "TTLS",
+            EapMethod::Tls => "TLS",
+            EapMethod::Pwd => "PWD",
+            EapMethod::Fast => "FAST",
+            EapMethod::Leap => "LEAP",
+            EapMethod::Md5 => "MD5",
+            EapMethod::MschapV2 => "MSCHAPV2",
+            EapMethod::Gtc => "GTC",
+            EapMethod::Otp => "OTP",
+            EapMethod::Sim => "SIM",
+            EapMethod::Aka => "AKA",
+            EapMethod::AkaPrime => "AKA'",
+        };
+        write!(f, "{}", str)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Credential that wpa_supplicant requests interactively (`CTRL-REQ-<field>`) while connecting.
+pub enum CredentialField {
+    Identity,
+    Password,
+    NewPassword,
+    Pin,
+    Otp,
+    Passphrase,
+    Sim,
+    PskPassphrase,
+    ExtCertCheck,
+    Other(String),
+}
+
+impl FromStr for CredentialField {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Ok(match s {
+            "IDENTITY" => CredentialField::Identity,
+            "PASSWORD" => CredentialField::Password,
+            "NEW_PASSWORD" => CredentialField::NewPassword,
+            "PIN" => CredentialField::Pin,
+            "OTP" => CredentialField::Otp,
+            "PASSPHRASE" => CredentialField::Passphrase,
+            "SIM" => CredentialField::Sim,
+            "PSK_PASSPHRASE" => CredentialField::PskPassphrase,
+            "EXT_CERT_CHECK" => CredentialField::ExtCertCheck,
+            other => CredentialField::Other(other.to_string()),
+        })
+    }
+}
+
+impl Display for CredentialField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            CredentialField::Identity => "IDENTITY",
+            CredentialField::Password => "PASSWORD",
+            CredentialField::NewPassword => "NEW_PASSWORD",
+            CredentialField::Pin => "PIN",
+            CredentialField::Otp => "OTP",
+            CredentialField::Passphrase => "PASSPHRASE",
+            CredentialField::Sim => "SIM",
+            CredentialField::PskPassphrase => "PSK_PASSPHRASE",
+            CredentialField::ExtCertCheck => "EXT_CERT_CHECK",
+            CredentialField::Other(other) => other,
         };
         write!(f, "{}", str)
     }
 }
+
+/// Format a list network parameter (eg: `key_mgmt` or `eap`) as space separated values.
+pub(crate) fn join_values<T: Display>(values: &[T]) -> String {
+    values
+        .iter()
+        .map(|value| value.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Format a string network parameter for `SET_NETWORK`. wpa_supplicant takes everything up to
+/// the last double quote literally, so only control characters need special treatment; those
+/// values are sent hex encoded instead.
+pub(crate) fn quote_network_value(value: &str) -> String {
+    if value.chars().any(char::is_control) {
+        value.bytes().map(|b| format!("{b:02x}")).collect()
+    } else {
+        format!("\"{value}\"")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // `BSS` output of wpa_supplicant 2.10 for a WPA2 access point
+    const BSS_RESPONSE: &str = "id=3
+bssid=02:00:00:00:01:00
+freq=2412
+beacon_int=100
+capabilities=0x0411
+qual=0
+noise=-92
+level=-45
+tsf=0000001234567890
+age=3
+ie=0008746573742d6e6574030101
+flags=[WPA2-PSK-CCMP][ESS]
+ssid=test-net
+snr=47
+est_throughput=65000
+update_idx=12
+beacon_ie=0008746573742d6e6574030101";
+
+    #[test]
+    fn parses_bss_response() {
+        let bss = BssInfo::from_response(BSS_RESPONSE).unwrap();
+        assert_eq!(bss.id, 3);
+        assert_eq!(bss.bssid, MacAddr::from_str("02:00:00:00:01:00").unwrap());
+        assert_eq!(bss.freq, Some(Frequency(2412)));
+        assert_eq!(bss.beacon_interval, Some(100));
+        assert_eq!(bss.capabilities, Some(0x0411));
+        assert_eq!(bss.qual, Some(0));
+        assert_eq!(bss.noise, Some(-92));
+        assert_eq!(bss.level, Some(-45));
+        assert_eq!(bss.snr, Some(47));
+        assert_eq!(bss.tsf, Some(1234567890));
+        assert_eq!(bss.age, Some(3));
+        assert_eq!(bss.est_throughput, Some(65000));
+        assert_eq!(bss.flags.as_deref(), Some("[WPA2-PSK-CCMP][ESS]"));
+        assert_eq!(bss.ssid.as_deref(), Some("test-net"));
+        assert_eq!(bss.ie, b"\x00\x08test-net\x03\x01\x01");
+        assert_eq!(bss.beacon_ie, bss.ie);
+        assert!(bss.anqp.is_empty());
+        assert_eq!(
+            bss.extra,
+            HashMap::from([("update_idx".to_string(), "12".to_string())])
+        );
+    }
+
+    fn protocol(
+        protocol: Protocol,
+        key_mgmt: &[AkmSuite],
+        pairwise_ciphers: &[CipherSuite],
+        preauth: bool,
+    ) -> SecurityProtocol {
+        SecurityProtocol {
+            protocol,
+            key_mgmt: key_mgmt.to_vec(),
+            pairwise_ciphers: pairwise_ciphers.to_vec(),
+            preauth,
+        }
+    }
+
+    #[test]
+    fn parses_security_flags() {
+        let cases = [
+            (
+                "[WPA2-EAP-SUITE-B-192-GCMP-256][ESS]",
+                SecurityFlags {
+                    protocols: vec![protocol(
+                        Protocol::Rsn,
+                        &[AkmSuite::SuiteB192],
+                        &[CipherSuite::Gcmp256],
+                        false,
+                    )],
+                    ess: true,
+                    ..Default::default()
+                },
+            ),
+            (
+                "[WPA2-PSK+SAE-CCMP-preauth][SAE-H2E][ESS]",
+                SecurityFlags {
+                    protocols: vec![protocol(
+                        Protocol::Rsn,
+                        &[AkmSuite::Psk, AkmSuite::Sae],
+                        &[CipherSuite::Ccmp],
+                        true,
+                    )],
+                    ess: true,
+                    sae_h2e: true,
+                    ..Default::default()
+                },
+            ),
+            (
+                "[RSN-SAE-CCMP][MESH]",
+                SecurityFlags {
+                    protocols: vec![protocol(
+                        Protocol::Rsn,
+                        &[AkmSuite::Sae],
+                        &[CipherSuite::Ccmp],
+                        false,
+                    )],
+                    mesh: true,
+                    ..Default::default()
+                },
+            ),
+            (
+                "[WPA-PSK-TKIP][WPA2-PSK-CCMP+TKIP][WPS][ESS]",
+                SecurityFlags {
+                    protocols: vec![
+                        protocol(Protocol::Wpa, &[AkmSuite::Psk], &[CipherSuite::Tkip], false),
+                        protocol(
+                            Protocol::Rsn,
+                            &[AkmSuite::Psk],
+                            &[CipherSuite::Ccmp, CipherSuite::Tkip],
+                            false,
+                        ),
+                    ],
+                    wps: true,
+                    ess: true,
+                    ..Default::default()
+                },
+            ),
+            (
+                "[OWE-TRANS-OPEN][ESS]",
+                SecurityFlags {
+                    owe_transition: true,
+                    ess: true,
+                    ..Default::default()
+                },
+            ),
+            (
+                "[WPA2-PSK-NONE][ESS]",
+                SecurityFlags {
+                    protocols: vec![protocol(
+                        Protocol::Rsn,
+                        &[AkmSuite::Psk],
+                        &[CipherSuite::UseGroup],
+                        false,
+                    )],
+                    ess: true,
+                    ..Default::default()
+                },
+            ),
+            (
+                "[WPA2-UNKNOWN-CCMP][ESS]",
+                SecurityFlags {
+                    ess: true,
+                    other: vec!["WPA2-UNKNOWN-CCMP".into()],
+                    ..Default::default()
+                },
+            ),
+        ];
+        for (flags, expected) in cases {
+            assert_eq!(
+                SecurityFlags::from_flags(flags),
+                expected,
+                "parsing {flags}"
+            );
+        }
+    }
+
+    #[test]
+    fn finds_open_networks() {
+        let cases = [
+            ("[ESS]", true),
+            ("[OWE-TRANS-OPEN][ESS]", true),
+            ("[WEP][ESS]", false),
+            ("[WPA2-PSK-NONE][ESS]", false),
+            ("[WPA2-UNKNOWN-CCMP][ESS]", false),
+            ("[RSN-SAE-CCMP][MESH]", false),
+        ];
+        for (flags, expected) in cases {
+            assert_eq!(
+                SecurityFlags::from_flags(flags).is_open(),
+                expected,
+                "parsing {flags}"
+            );
+        }
+    }
+
+    fn mac(s: &str) -> Option<MacAddr> {
+        Some(MacAddr::from_str(s).unwrap())
+    }
+
+    #[test]
+    fn parses_connection_events() {
+        let cases = [
+            (
+                "CTRL-EVENT-CONNECTED - Connection to 02:00:00:00:01:00 completed [id=0 id_str=home]",
+                ConnectionInfo {
+                    bssid: mac("02:00:00:00:01:00"),
+                    network_id: Some(0),
+                    id_str: Some("home".into()),
+                },
+            ),
+            (
+                "CTRL-EVENT-CONNECTED - Connection to 02:00:00:00:01:00 completed [id=3 id_str=]",
+                ConnectionInfo {
+                    bssid: mac("02:00:00:00:01:00"),
+                    network_id: Some(3),
+                    id_str: None,
+                },
+            ),
+            (
+                "CTRL-EVENT-CONNECTED - Connection to 02:00:00:00:01:00 completed",
+                ConnectionInfo {
+                    bssid: mac("02:00:00:00:01:00"),
+                    network_id: None,
+                    id_str: None,
+                },
+            ),
+        ];
+        for (event, expected) in cases {
+            assert_eq!(
+                ConnectionInfo::from_event(event),
+                expected,
+                "parsing {event}"
+            );
+        }
+    }
+
+    #[test]
+    fn parses_disconnection_events() {
+        let cases = [
+            (
+                "CTRL-EVENT-DISCONNECTED bssid=02:00:00:00:01:00 reason=3 locally_generated=1",
+                DisconnectionInfo {
+                    bssid: mac("02:00:00:00:01:00"),
+                    reason: Some(ReasonCode::from(3)),
+                    locally_generated: true,
+                },
+            ),
+            (
+                "CTRL-EVENT-DISCONNECTED bssid=02:00:00:00:01:00 reason=15",
+                DisconnectionInfo {
+                    bssid: mac("02:00:00:00:01:00"),
+                    reason: Some(ReasonCode::from(15)),
+                    locally_generated: false,
+                },
+            ),
+            (
+                "CTRL-EVENT-DISCONNECTED bssid=not-a-mac",
+                DisconnectionInfo {
+                    bssid: None,
+                    reason: None,
+                    locally_generated: false,
+                },
+            ),
+        ];
+        for (event, expected) in cases {
+            assert_eq!(
+                DisconnectionInfo::from_event(event),
+                expected,
+                "parsing {event}"
+            );
+        }
+    }
+
+    #[test]
+    fn parses_reject_events() {
+        let cases = [
+            (
+                "CTRL-EVENT-ASSOC-REJECT bssid=02:00:00:00:01:00 status_code=17",
+                RejectInfo {
+                    bssid: mac("02:00:00:00:01:00"),
+                    status_code: Some(17),
+                },
+            ),
+            (
+                "CTRL-EVENT-AUTH-REJECT 02:00:00:00:01:00 auth_type=3 auth_transaction=1 status_code=15",
+                RejectInfo {
+                    bssid: mac("02:00:00:00:01:00"),
+                    status_code: Some(15),
+                },
+            ),
+            (
+                "CTRL-EVENT-ASSOC-REJECT status_code=1",
+                RejectInfo {
+                    bssid: None,
+                    status_code: Some(1),
+                },
+            ),
+        ];
+        for (event, expected) in cases {
+            assert_eq!(RejectInfo::from_event(event), expected, "parsing {event}");
+        }
+    }
+
+    #[test]
+    fn parses_temp_disabled_events() {
+        let cases = [
+            (
+                r#"CTRL-EVENT-SSID-TEMP-DISABLED id=0 ssid="home" auth_failures=1 duration=10 reason=WRONG_KEY"#,
+                TempDisabledInfo {
+                    network_id: Some(0),
+                    ssid: Some("home".into()),
+                    auth_failures: Some(1),
+                    duration: Some(10),
+                    reason: Some("WRONG_KEY".into()),
+                },
+            ),
+            (
+                r#"CTRL-EVENT-SSID-TEMP-DISABLED id=2 ssid="caf\xc3\xa9 \"x\"" auth_failures=2 duration=20 reason=CONN_FAILED"#,
+                TempDisabledInfo {
+                    network_id: Some(2),
+                    ssid: Some("café \"x\"".into()),
+                    auth_failures: Some(2),
+                    duration: Some(20),
+                    reason: Some("CONN_FAILED".into()),
+                },
+            ),
+            (
+                "CTRL-EVENT-SSID-TEMP-DISABLED id=1",
+                TempDisabledInfo {
+                    network_id: Some(1),
+                    ssid: None,
+                    auth_failures: None,
+                    duration: None,
+                    reason: None,
+                },
+            ),
+        ];
+        for (event, expected) in cases {
+            assert_eq!(
+                TempDisabledInfo::from_event(event),
+                expected,
+                "parsing {event}"
+            );
+        }
+    }
+
+    #[test]
+    fn finds_channel_width() {
+        // VHT operation element: channel width, center frequency segments 0 and 1, basic MCS set
+        let cases = [
+            ([0, 0, 0], ChannelWidth::Mhz20),
+            ([1, 42, 0], ChannelWidth::Mhz80),
+            ([1, 42, 50], ChannelWidth::Mhz160),
+            ([1, 42, 155], ChannelWidth::Mhz80Plus80),
+            ([2, 50, 0], ChannelWidth::Mhz160),
+            ([3, 42, 155], ChannelWidth::Mhz80Plus80),
+        ];
+        for (vht, expected) in cases {
+            let bss = BssInfo {
+                ie: [&[192, 5][..], &vht, &[0xfc, 0xff]].concat(),
+                ..BssInfo::from_response(BSS_RESPONSE).unwrap()
+            };
+            assert_eq!(bss.channel_width(), expected, "VHT operation {vht:?}");
+        }
+    }
+}